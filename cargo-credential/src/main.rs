@@ -1,4 +1,174 @@
-use cargo_credential::{Action, CredentialResponse, Error, RegistryInfo, Secret};
+use std::{os::unix::ffi::OsStringExt, path::PathBuf};
+
+use cargo_credential::{
+    Action, CacheControl, CredentialResponse, Error, Operation, RegistryInfo, Secret,
+};
+use oo7::Keyring;
+use pasetors::{
+    keys::{AsymmetricPublicKey, AsymmetricSecretKey},
+    paserk::{FormatAsPaserk, Id},
+    version3::{PublicToken, V3},
+};
+use serde::Serialize;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+/// The keyring secret used with `--keyring`.
+const SECRET_ENV: &str = "OO7_KEYRING_SECRET";
+
+/// Prefix of the PASERK secret keys used for asymmetric tokens.
+const PASERK_SECRET_PREFIX: &str = "k3.secret.";
+
+fn other(err: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::Other(Box::new(err))
+}
+
+/// The arguments given in the `credential-provider` configuration.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    /// Alias or label of the collection to use instead of the default one.
+    collection: Option<String>,
+    /// Keyring file to use instead of the Secret Service.
+    keyring: Option<PathBuf>,
+    /// The `sub` claim of asymmetric tokens.
+    key_subject: Option<String>,
+}
+
+impl Options {
+    fn parse(args: &[&str]) -> Result<Self, Error> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, value),
+                None => match args.next() {
+                    Some(value) => (*arg, *value),
+                    None => return Err(format!("missing value for {arg}").into()),
+                },
+            };
+            let value = value.to_owned();
+            match name {
+                "--collection" => options.collection = Some(value),
+                "--keyring" => options.keyring = Some(value.into()),
+                "--key-subject" => options.key_subject = Some(value),
+                _ => return Err(format!("unknown argument {name}").into()),
+            }
+        }
+        if options.collection.is_some() && options.keyring.is_some() {
+            return Err("--collection and --keyring can't be used together".into());
+        }
+        Ok(options)
+    }
+
+    async fn keyring(&self) -> Result<Keyring, Error> {
+        let keyring = if let Some(path) = &self.keyring {
+            let secret = std::env::var_os(SECRET_ENV)
+                .ok_or_else(|| format!("--keyring requires the keyring secret in {SECRET_ENV}"))?;
+            Keyring::builder()
+                .backend(oo7::Backend::File)
+                .path(path)
+                .secret(secret.into_vec())
+                .build()
+                .await
+                .map_err(other)?
+        } else if let Some(name) = &self.collection {
+            let service = oo7::dbus::Service::new().await.map_err(other)?;
+            let collection = match service.with_alias(name).await.map_err(other)? {
+                Some(collection) => collection,
+                None => service
+                    .with_label(name)
+                    .await
+                    .map_err(other)?
+                    .ok_or_else(|| format!("collection {name} not found"))?,
+            };
+            Keyring::DBus(collection)
+        } else {
+            Keyring::new().await.map_err(other)?
+        };
+        if keyring.is_locked().await.map_err(other)? {
+            keyring.unlock().await.map_err(other)?;
+        }
+        Ok(keyring)
+    }
+}
+
+/// The claims of an asymmetric token, as defined by RFC 3231.
+#[derive(Serialize)]
+struct Message<'a> {
+    iat: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mutation: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vers: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cksum: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Footer<'a> {
+    url: &'a str,
+    kip: String,
+}
+
+fn secret_key(paserk: &str) -> Result<AsymmetricSecretKey<V3>, Error> {
+    AsymmetricSecretKey::<V3>::try_from(paserk).map_err(other)
+}
+
+/// The PASERK public key to register with the registry.
+fn public_key(secret_key: &AsymmetricSecretKey<V3>) -> Result<String, Error> {
+    let public_key = AsymmetricPublicKey::<V3>::try_from(secret_key).map_err(other)?;
+    let mut paserk = String::new();
+    FormatAsPaserk::fmt(&public_key, &mut paserk).map_err(other)?;
+    Ok(paserk)
+}
+
+/// Sign a token that is only valid for the given operation.
+fn asymmetric_token(
+    paserk: &str,
+    registry: &RegistryInfo<'_>,
+    operation: &Operation<'_>,
+    subject: Option<&str>,
+) -> Result<String, Error> {
+    let secret_key = secret_key(paserk)?;
+    let public_key = AsymmetricPublicKey::<V3>::try_from(&secret_key).map_err(other)?;
+    let mut kip = String::new();
+    FormatAsPaserk::fmt(&Id::from(&public_key), &mut kip).map_err(other)?;
+
+    let (mutation, name, vers, cksum) = match operation {
+        Operation::Read => (None, None, None, None),
+        Operation::Publish { name, vers, cksum } => {
+            (Some("publish"), Some(*name), Some(*vers), Some(*cksum))
+        }
+        Operation::Yank { name, vers } => (Some("yank"), Some(*name), Some(*vers), None),
+        Operation::Unyank { name, vers } => (Some("unyank"), Some(*name), Some(*vers), None),
+        Operation::Owners { name } => (Some("owners"), Some(*name), None, None),
+        _ => return Err(Error::OperationNotSupported),
+    };
+    let iat = OffsetDateTime::now_utc().format(&Rfc3339).map_err(other)?;
+    let message = Message {
+        iat: &iat,
+        sub: subject,
+        mutation,
+        name,
+        vers,
+        cksum,
+    };
+    let footer = Footer {
+        url: registry.index_url,
+        kip,
+    };
+
+    PublicToken::sign(
+        &secret_key,
+        serde_json::to_string(&message).map_err(other)?.as_bytes(),
+        Some(serde_json::to_string(&footer).map_err(other)?.as_bytes()),
+        None,
+    )
+    .map_err(other)
+}
 
 pub struct SecretServiceCredential;
 
@@ -7,74 +177,75 @@ impl SecretServiceCredential {
         &self,
         registry: &RegistryInfo<'_>,
         action: &Action<'_>,
+        options: &Options,
     ) -> Result<CredentialResponse, Error> {
-        let service = oo7::dbus::Service::new()
-            .await
-            .map_err(|err| Error::Other(Box::new(err)))?;
-        let collection = service
-            .default_collection()
-            .await
-            .map_err(|err| Error::Other(Box::new(err)))?;
+        let keyring = options.keyring().await?;
         let attributes = &[("url", registry.index_url)];
-        let items = collection
-            .search_items(attributes)
-            .await
-            .map_err(|err| Error::Other(Box::new(err)))?;
+        let items = keyring.search_items(attributes).await.map_err(other)?;
 
         match action {
-            cargo_credential::Action::Get(_) => {
-                if items.is_empty() {
+            Action::Get(operation) => {
+                let Some(item) = items.first() else {
                     return Err(Error::NotFound);
-                }
+                };
+                let secret = item.secret().await.map_err(other)?;
+                let secret = std::str::from_utf8(&secret).map_err(other)?;
 
-                let token = Secret::from(
-                    std::str::from_utf8(
-                        &items[0]
-                            .secret()
-                            .await
-                            .map_err(|err| Error::Other(Box::new(err)))?,
-                    )
-                    .unwrap()
-                    .to_owned(),
-                );
-
-                Ok(CredentialResponse::Get {
-                    token,
-                    cache: cargo_credential::CacheControl::Session,
-                    operation_independent: true,
-                })
+                if secret.starts_with(PASERK_SECRET_PREFIX) {
+                    // Asymmetric tokens are bound to the operation and their
+                    // creation time
+                    let token = asymmetric_token(
+                        secret,
+                        registry,
+                        operation,
+                        options.key_subject.as_deref(),
+                    )?;
+                    Ok(CredentialResponse::Get {
+                        token: Secret::from(token),
+                        cache: CacheControl::Never,
+                        operation_independent: false,
+                    })
+                } else {
+                    Ok(CredentialResponse::Get {
+                        token: Secret::from(secret.to_owned()),
+                        cache: CacheControl::Session,
+                        operation_independent: true,
+                    })
+                }
             }
-            cargo_credential::Action::Login(options) => {
-                let token = cargo_credential::read_token(options, registry)?.expose();
+            Action::Login(login_options) => {
+                let token = cargo_credential::read_token(login_options, registry)?.expose();
+                if token.starts_with(PASERK_SECRET_PREFIX) {
+                    let public_key = public_key(&secret_key(&token)?)?;
+                    eprintln!("the public key to register is {public_key}");
+                }
 
-                if let Some(item) = items.first() {
-                    item.set_secret(token)
-                        .await
-                        .map_err(|err| Error::Other(Box::new(err)))?;
-                } else {
-                    collection
-                        .create_item(
-                            &format!("cargo-registry:{}", registry.index_url),
-                            attributes,
-                            token,
-                            true,
-                            None,
-                        )
-                        .await
-                        .map_err(|err| Error::Other(Box::new(err)))?;
+                let mut attributes = vec![("url", registry.index_url)];
+                if let Some(name) = registry.name {
+                    attributes.push(("registry", name));
                 }
+                // Replace the items stored without the registry name as well
+                keyring
+                    .delete(&[("url", registry.index_url)])
+                    .await
+                    .map_err(other)?;
+                keyring
+                    .create_item(
+                        &format!("cargo-registry:{}", registry.index_url),
+                        &attributes,
+                        token,
+                        true,
+                    )
+                    .await
+                    .map_err(other)?;
 
                 Ok(CredentialResponse::Login)
             }
-            cargo_credential::Action::Logout => {
+            Action::Logout => {
                 if items.is_empty() {
                     return Err(Error::NotFound);
                 }
-
-                items[0]
-                    .delete(None)
-                    .await
-                    .map_err(|err| Error::Other(Box::new(err)))?;
+                keyring.delete(attributes).await.map_err(other)?;
                 Ok(CredentialResponse::Logout)
             }
             _ => Err(Error::OperationNotSupported),
@@ -87,13 +258,66 @@ impl cargo_credential::Credential for SecretServiceCredential {
         &self,
         registry: &RegistryInfo<'_>,
         action: &Action<'_>,
-        _args: &[&str],
+        args: &[&str],
     ) -> Result<CredentialResponse, Error> {
+        let options = Options::parse(args)?;
         let rt = tokio::runtime::Runtime::new().unwrap();
-        rt.block_on(async move { self.preform_future(registry, action).await })
+        rt.block_on(async move { self.preform_future(registry, action, &options).await })
     }
 }
 
 fn main() {
     cargo_credential::main(SecretServiceCredential {});
 }
+
+#[cfg(test)]
+mod tests {
+    use pasetors::token::UntrustedToken;
+
+    use super::*;
+
+    #[test]
+    fn parse_options() {
+        let options = Options::parse(&["--collection", "work", "--key-subject=ci"]).unwrap();
+        assert_eq!(options.collection.as_deref(), Some("work"));
+        assert_eq!(options.key_subject.as_deref(), Some("ci"));
+        assert!(options.keyring.is_none());
+
+        assert!(Options::parse(&["--keyring"]).is_err());
+        assert!(Options::parse(&["--unknown", "value"]).is_err());
+        assert!(Options::parse(&["--collection=a", "--keyring=b"]).is_err());
+    }
+
+    #[test]
+    fn asymmetric() {
+        let paserk = "k3.secret.AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEB";
+        let registry = RegistryInfo {
+            index_url: "sparse+https://registry.example.com/index/",
+            name: Some("example"),
+            headers: vec![],
+        };
+        let operation = Operation::Yank {
+            name: "oo7",
+            vers: "0.1.0",
+        };
+        let token = asymmetric_token(paserk, &registry, &operation, Some("ci")).unwrap();
+
+        let public_key = public_key(&secret_key(paserk).unwrap()).unwrap();
+        assert!(public_key.starts_with("k3.public."));
+        let public_key = AsymmetricPublicKey::<V3>::try_from(public_key.as_str()).unwrap();
+        let token = UntrustedToken::try_from(token.as_str()).unwrap();
+        let trusted =
+            PublicToken::verify(&public_key, &token, Some(token.untrusted_footer()), None).unwrap();
+        let claims = serde_json::from_str::<serde_json::Value>(trusted.payload()).unwrap();
+        assert_eq!(claims["sub"], "ci");
+        assert_eq!(claims["mutation"], "yank");
+        assert_eq!(claims["name"], "oo7");
+        assert_eq!(claims["vers"], "0.1.0");
+        assert!(claims.get("cksum").is_none());
+
+        assert!(matches!(
+            asymmetric_token(paserk, &registry, &Operation::Unknown, None),
+            Err(Error::OperationNotSupported)
+        ));
+    }
+}
@@ -0,0 +1,316 @@
+//! Portable archive format used by the `export` and `import` commands.
+//!
+//! A plain archive is a JSON document:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "items": [
+//!     {
+//!       "label": "My Personal Mail",
+//!       "attributes": { "smtp-port": "1025" },
+//!       "content_type": "text/plain",
+//!       "created": 1700000000,
+//!       "modified": 1700000000,
+//!       "secret": "68756e74657232"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `created` and `modified` are UNIX timestamps in seconds and `secret` is
+//! the hex encoded secret.
+//!
+//! Both timestamps are restored when importing into a keyring file. The
+//! Secret Service API doesn't allow setting them, so items imported into a
+//! collection are created and modified at the time of the import.
+//!
+//! An encrypted archive is a regular keyring file, protected by the
+//! passphrase, holding the JSON document as the secret of a single item with
+//! the [`ARCHIVE_SCHEMA`] schema.
+
+use std::{
+    collections::HashMap, io::Write, os::unix::fs::OpenOptionsExt, path::Path, time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// Version of the archive format.
+const ARCHIVE_VERSION: u32 = 1;
+/// Schema of the item holding the archive in an encrypted archive.
+const ARCHIVE_SCHEMA: &str = "org.oo7.Archive";
+/// Header of the keyring files, used to detect encrypted archives.
+const KEYRING_FILE_HEADER: &[u8] = b"GnomeKeyring\n\r\0\n";
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveItem {
+    pub label: String,
+    pub attributes: HashMap<String, String>,
+    pub content_type: String,
+    pub created: u64,
+    pub modified: u64,
+    secret: String,
+}
+
+impl ArchiveItem {
+    fn new(
+        label: String,
+        mut attributes: HashMap<String, String>,
+        secret: &oo7::Secret,
+        created: Duration,
+        modified: Duration,
+    ) -> Self {
+        attributes.remove(oo7::CONTENT_TYPE_ATTRIBUTE);
+
+        Self {
+            label,
+            attributes,
+            content_type: secret.content_type().as_str().to_owned(),
+            created: created.as_secs(),
+            modified: modified.as_secs(),
+            secret: hex::encode(secret.as_bytes()),
+        }
+    }
+
+    pub fn from_file_item(item: &oo7::file::UnlockedItem) -> Self {
+        Self::new(
+            item.label().to_owned(),
+            item.attributes().clone(),
+            &item.secret(),
+            item.created(),
+            item.modified(),
+        )
+    }
+
+    pub async fn from_dbus_item(item: &oo7::dbus::Item) -> Result<Self, Error> {
+        Ok(Self::new(
+            item.label().await?,
+            item.attributes().await?,
+            &item.secret().await?,
+            item.created().await?,
+            item.modified().await?,
+        ))
+    }
+
+    /// The item to insert into a keyring file, keeping its timestamps.
+    pub fn to_file_item(&self) -> Result<oo7::file::UnlockedItem, Error> {
+        Ok(
+            oo7::file::UnlockedItem::new(&self.label, &self.attributes, self.secret()?)
+                .with_timestamps(
+                    Duration::from_secs(self.created),
+                    Duration::from_secs(self.modified),
+                ),
+        )
+    }
+
+    pub fn secret(&self) -> Result<oo7::Secret, Error> {
+        let content_type = self
+            .content_type
+            .parse::<oo7::ContentType>()
+            .map_err(Error::Owned)?;
+        let bytes = hex::decode(&self.secret)
+            .map_err(|err| Error::Owned(format!("Invalid secret of '{}': {err}", self.label)))?;
+
+        Ok(oo7::Secret::with_content_type(content_type, bytes))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Archive {
+    version: u32,
+    pub items: Vec<ArchiveItem>,
+}
+
+impl Archive {
+    pub fn new(items: Vec<ArchiveItem>) -> Self {
+        Self {
+            version: ARCHIVE_VERSION,
+            items,
+        }
+    }
+
+    /// Write the archive as a plain JSON document.
+    ///
+    /// The file must not exist yet and is only readable by the user.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|err| Error::Owned(format!("Failed to serialize the archive: {err}")))?;
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(&json)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Write the archive as a keyring file protected by `passphrase`.
+    pub async fn write_encrypted(&self, path: &Path, passphrase: oo7::Secret) -> Result<(), Error> {
+        if path.exists() {
+            return Err(Error::Owned(format!("{} already exists", path.display())));
+        }
+        let json = serde_json::to_vec(self)
+            .map_err(|err| Error::Owned(format!("Failed to serialize the archive: {err}")))?;
+
+        let keyring = oo7::file::UnlockedKeyring::load(path, passphrase).await?;
+        keyring
+            .create_item(
+                "oo7-cli archive",
+                &[(oo7::XDG_SCHEMA_ATTRIBUTE, ARCHIVE_SCHEMA)],
+                oo7::Secret::blob(json),
+                true,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Whether the file at `path` is an encrypted archive.
+    pub fn is_encrypted(path: &Path) -> Result<bool, Error> {
+        let content = std::fs::read(path)?;
+        Ok(content.starts_with(KEYRING_FILE_HEADER))
+    }
+
+    /// Read a plain archive.
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let content = std::fs::read(path)?;
+        Self::from_slice(&content)
+    }
+
+    /// Read an encrypted archive using `passphrase`.
+    pub async fn read_encrypted(path: &Path, passphrase: oo7::Secret) -> Result<Self, Error> {
+        let keyring = oo7::file::UnlockedKeyring::load(path, passphrase).await?;
+        let item = keyring
+            .lookup_item(&[(oo7::XDG_SCHEMA_ATTRIBUTE, ARCHIVE_SCHEMA)])
+            .await?
+            .ok_or_else(|| Error::new("The keyring file is not an oo7-cli archive"))?;
+        Self::from_slice(item.secret().as_bytes())
+    }
+
+    fn from_slice(content: &[u8]) -> Result<Self, Error> {
+        let archive: Self = serde_json::from_slice(content)
+            .map_err(|err| Error::Owned(format!("Invalid archive: {err}")))?;
+        if archive.version != ARCHIVE_VERSION {
+            return Err(Error::Owned(format!(
+                "Unsupported archive version {}",
+                archive.version
+            )));
+        }
+        Ok(archive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive() -> Archive {
+        Archive::new(vec![
+            ArchiveItem::new(
+                "Mail".to_owned(),
+                HashMap::from([("service".to_owned(), "mail".to_owned())]),
+                &oo7::Secret::text("hunter2"),
+                Duration::from_secs(1_700_000_000),
+                Duration::from_secs(1_700_000_100),
+            ),
+            ArchiveItem::new(
+                "Token".to_owned(),
+                HashMap::new(),
+                &oo7::Secret::blob([0, 1, 2, 255]),
+                Duration::from_secs(1_600_000_000),
+                Duration::from_secs(1_600_000_000),
+            ),
+        ])
+    }
+
+    fn assert_same_items(archive: &Archive, expected: &Archive) {
+        assert_eq!(archive.items.len(), expected.items.len());
+        for (item, expected) in archive.items.iter().zip(&expected.items) {
+            assert_eq!(item.label, expected.label);
+            assert_eq!(item.attributes, expected.attributes);
+            assert_eq!(item.content_type, expected.content_type);
+            assert_eq!(item.created, expected.created);
+            assert_eq!(item.modified, expected.modified);
+            assert_eq!(
+                item.secret().unwrap().as_bytes(),
+                expected.secret().unwrap().as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn plain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.json");
+        let archive = archive();
+
+        archive.write(&path).unwrap();
+        assert!(!Archive::is_encrypted(&path).unwrap());
+        assert_same_items(&Archive::read(&path).unwrap(), &archive);
+
+        // Never overwrite an existing file
+        assert!(archive.write(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.keyring");
+        let archive = archive();
+
+        archive
+            .write_encrypted(&path, oo7::Secret::text("passphrase"))
+            .await
+            .unwrap();
+        assert!(Archive::is_encrypted(&path).unwrap());
+        let read = Archive::read_encrypted(&path, oo7::Secret::text("passphrase"))
+            .await
+            .unwrap();
+        assert_same_items(&read, &archive);
+
+        assert!(
+            Archive::read_encrypted(&path, oo7::Secret::text("wrong"))
+                .await
+                .is_err()
+        );
+        assert!(
+            archive
+                .write_encrypted(&path, oo7::Secret::text("passphrase"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn file_item() {
+        let archive = archive();
+        let keyring = oo7::file::UnlockedKeyring::temporary(oo7::Secret::text("test"))
+            .await
+            .unwrap();
+        let items = archive
+            .items
+            .iter()
+            .map(ArchiveItem::to_file_item)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        keyring.insert_items(items, false).await.unwrap();
+
+        let exported = keyring
+            .items()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|item| ArchiveItem::from_file_item(&item))
+            .collect();
+        assert_same_items(&Archive::new(exported), &archive);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        assert!(Archive::from_slice(br#"{"version": 2, "items": []}"#).is_err());
+        assert!(Archive::from_slice(b"not json").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Support of the `exec` command.
+//!
+//! The secrets are handed to the child process either as environment
+//! variables or as files in a private directory inside `$XDG_RUNTIME_DIR`,
+//! which is usually a tmpfs. The directory is removed once the child exits,
+//! even when it is interrupted.
+
+use std::{
+    ffi::OsStr,
+    io::Write,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{DirBuilderExt, OpenOptionsExt},
+        process::ExitStatusExt,
+    },
+    path::PathBuf,
+    process::Command,
+};
+
+use tokio::signal::unix::{SignalKind, signal};
+
+use crate::Error;
+
+/// A `VAR=key=value,key2=value2` mapping of a variable to the attributes of
+/// the item holding its value.
+#[derive(Clone, Debug)]
+pub struct Mapping {
+    pub variable: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl std::str::FromStr for Mapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (variable, attributes) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid VAR=key=value mapping: no `=` found in `{s}`"))?;
+        // The name is also used for the file name when passed as a file
+        if variable.is_empty()
+            || !variable
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(format!("Invalid variable name in `{s}`"));
+        }
+        let attributes = attributes
+            .split(',')
+            .map(|pair| {
+                pair.split_once('=')
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .ok_or_else(|| format!("Invalid key=value: no `=` found in `{pair}`"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            variable: variable.to_owned(),
+            attributes,
+        })
+    }
+}
+
+/// Private directory holding the secrets passed as files.
+struct SecretsDir(PathBuf);
+
+impl SecretsDir {
+    fn new() -> Result<Self, Error> {
+        let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .filter(|dir| !dir.is_empty())
+            .ok_or_else(|| Error::new("Passing secrets as files requires XDG_RUNTIME_DIR"))?;
+
+        let mut path = PathBuf::from(runtime_dir);
+        path.push(format!("oo7-cli-exec.{}", std::process::id()));
+        std::fs::DirBuilder::new().mode(0o700).create(&path)?;
+        Ok(Self(path))
+    }
+
+    fn write(&self, name: &str, secret: &oo7::Secret) -> Result<PathBuf, Error> {
+        let path = self.0.join(name);
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)?;
+        file.write_all(secret.as_bytes())?;
+        Ok(path)
+    }
+}
+
+impl Drop for SecretsDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run `command` with the secrets and return its exit code.
+///
+/// `variables` are set in the environment of the child, while `files` are
+/// written to a private directory and the variables point to their path.
+pub fn run(
+    command: &[String],
+    variables: Vec<(String, oo7::Secret)>,
+    files: Vec<(String, oo7::Secret)>,
+) -> Result<i32, Error> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| Error::new("No command to execute"))?;
+
+    let mut child = Command::new(program);
+    child.args(args);
+    for (variable, secret) in &variables {
+        child.env(variable, OsStr::from_bytes(secret.as_bytes()));
+    }
+
+    let secrets_dir = if files.is_empty() {
+        None
+    } else {
+        Some(SecretsDir::new()?)
+    };
+    if let Some(secrets_dir) = &secrets_dir {
+        for (variable, secret) in &files {
+            child.env(variable, secrets_dir.write(variable, secret)?);
+        }
+    }
+
+    // Ctrl-C and SIGTERM are handled instead of killing us along with the
+    // child, so that the secrets are removed once it exits. The handlers
+    // are reset in the child when executing the command.
+    let _signals = [SignalKind::interrupt(), SignalKind::terminate()]
+        .map(signal)
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let status = child
+        .status()
+        .map_err(|err| Error::Owned(format!("Failed to execute {program}: {err}")))?;
+    drop(secrets_dir);
+
+    // Follow the shell convention for children killed by a signal
+    Ok(status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1))
+}
@@ -0,0 +1,188 @@
+//! Random secrets generation used by the `generate` command.
+
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::Error;
+
+/// Used when no word list is given.
+const DEFAULT_WORDLIST: &str = "/usr/share/dict/words";
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CharClass {
+    Lowercase,
+    Uppercase,
+    Digits,
+    Symbols,
+}
+
+impl CharClass {
+    fn chars(self) -> &'static str {
+        match self {
+            Self::Lowercase => LOWERCASE,
+            Self::Uppercase => UPPERCASE,
+            Self::Digits => DIGITS,
+            Self::Symbols => SYMBOLS,
+        }
+    }
+}
+
+/// A uniformly distributed random number in `0..bound`.
+fn random_below(bound: usize) -> Result<usize, Error> {
+    assert!(bound > 0 && bound <= u32::MAX as usize);
+    let bound = bound as u32;
+    // Reject the values that would make the lowest numbers more likely
+    let zone = u32::MAX - (u32::MAX % bound);
+    loop {
+        let value = getrandom::u32()
+            .map_err(|err| Error::Owned(format!("Failed to generate random data: {err}")))?;
+        if value < zone {
+            return Ok((value % bound) as usize);
+        }
+    }
+}
+
+/// Generate a password of `length` characters using the given classes, with
+/// at least one character of each of them.
+pub fn password(length: usize, classes: &[CharClass]) -> Result<String, Error> {
+    if classes.is_empty() {
+        return Err(Error::new("At least one character class is required"));
+    }
+    if length < classes.len() {
+        return Err(Error::Owned(format!(
+            "The length must be at least {} to include all the character classes",
+            classes.len()
+        )));
+    }
+    let alphabet = classes
+        .iter()
+        .flat_map(|class| class.chars().chars())
+        .collect::<Vec<_>>();
+
+    loop {
+        let password = (0..length)
+            .map(|_| random_below(alphabet.len()).map(|index| alphabet[index]))
+            .collect::<Result<String, _>>()?;
+        let has_all_classes = classes
+            .iter()
+            .all(|class| password.chars().any(|c| class.chars().contains(c)));
+        if has_all_classes {
+            return Ok(password);
+        }
+    }
+}
+
+/// Generate a passphrase of `n_words` words picked from `wordlist`.
+///
+/// The word list contains a word per line. Lines of diceware lists, like
+/// `11111 abacus`, are supported as well.
+pub fn passphrase(
+    n_words: usize,
+    wordlist: Option<&Path>,
+    separator: &str,
+) -> Result<String, Error> {
+    if n_words == 0 {
+        return Err(Error::new("At least one word is required"));
+    }
+    let wordlist = wordlist.unwrap_or(Path::new(DEFAULT_WORDLIST));
+    let content = std::fs::read_to_string(wordlist).map_err(|err| {
+        Error::Owned(format!(
+            "Failed to read the word list {}: {err}",
+            wordlist.display()
+        ))
+    })?;
+    let mut words = content
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .collect::<Vec<_>>();
+    words.sort_unstable();
+    words.dedup();
+    if words.len() < 2 {
+        return Err(Error::Owned(format!(
+            "The word list {} doesn't contain enough words",
+            wordlist.display()
+        )));
+    }
+
+    let words = (0..n_words)
+        .map(|_| random_below(words.len()).map(|index| words[index]))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(words.join(separator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_below_bound() {
+        for bound in [1, 2, 3, 7, 26] {
+            for _ in 0..100 {
+                assert!(random_below(bound).unwrap() < bound);
+            }
+        }
+    }
+
+    #[test]
+    fn password_classes() {
+        let classes = [CharClass::Lowercase, CharClass::Digits];
+        for _ in 0..20 {
+            let generated = password(4, &classes).unwrap();
+            assert_eq!(generated.chars().count(), 4);
+            assert!(generated.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(generated.chars().any(|c| c.is_ascii_digit()));
+            assert!(
+                generated
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+            );
+        }
+
+        let generated = password(32, &[CharClass::Symbols]).unwrap();
+        assert!(generated.chars().all(|c| SYMBOLS.contains(c)));
+    }
+
+    #[test]
+    fn password_invalid() {
+        assert!(password(32, &[]).is_err());
+        assert!(password(1, &[CharClass::Lowercase, CharClass::Uppercase]).is_err());
+        assert!(password(0, &[CharClass::Lowercase]).is_err());
+    }
+
+    #[test]
+    fn passphrase_words() {
+        let dir = tempfile::tempdir().unwrap();
+        let wordlist = dir.path().join("words");
+        std::fs::write(&wordlist, "11111 abacus\n11112 abdomen\n11113 abdominal\n").unwrap();
+
+        let phrase = passphrase(5, Some(&wordlist), " ").unwrap();
+        let words = phrase.split(' ').collect::<Vec<_>>();
+        assert_eq!(words.len(), 5);
+        assert!(
+            words
+                .iter()
+                .all(|word| ["abacus", "abdomen", "abdominal"].contains(word))
+        );
+
+        let phrase = passphrase(1, Some(&wordlist), "-").unwrap();
+        assert!(!phrase.contains('-'));
+    }
+
+    #[test]
+    fn passphrase_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let wordlist = dir.path().join("words");
+
+        assert!(passphrase(4, Some(&wordlist), "-").is_err());
+        std::fs::write(&wordlist, "word\nword\n").unwrap();
+        assert!(passphrase(4, Some(&wordlist), "-").is_err());
+        std::fs::write(&wordlist, "one\ntwo\n").unwrap();
+        assert!(passphrase(0, Some(&wordlist), "-").is_err());
+    }
+}
@@ -1,17 +1,28 @@
+mod archive;
+mod exec;
+mod generate;
+mod secret_tool;
+
 use std::{
     collections::HashMap,
     fmt,
     io::{BufRead, IsTerminal, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{ExitCode, Termination},
     time::Duration,
 };
 
 use clap::{Args, Parser, Subcommand};
-use oo7::dbus::Service;
+use futures_util::{
+    FutureExt, StreamExt,
+    stream::{self, FuturesUnordered, LocalBoxStream},
+};
+use oo7::{dbus::Service, zbus::zvariant::OwnedObjectPath};
 use serde::Serialize;
 use time::{OffsetDateTime, UtcOffset};
 
+use crate::archive::{Archive, ArchiveItem};
+
 const BINARY_NAME: &str = env!("CARGO_BIN_NAME");
 const H_STYLE: anstyle::Style = anstyle::Style::new().bold().underline();
 
@@ -61,6 +72,9 @@ impl Termination for Error {
 
 #[derive(Serialize)]
 struct ItemOutput {
+    /// The object path of Secret Service items
+    #[serde(skip)]
+    path: Option<String>,
     label: String,
     /// Those are None if the item is locked
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -88,24 +102,6 @@ impl ItemOutput {
         is_locked: bool,
         as_hex: bool,
     ) -> Self {
-        let local_offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
-
-        let created = created.map(|created| {
-            OffsetDateTime::from_unix_timestamp(created.as_secs() as i64)
-                .unwrap()
-                .to_offset(local_offset)
-        });
-        let modified = modified.map(|modified| {
-            OffsetDateTime::from_unix_timestamp(modified.as_secs() as i64)
-                .unwrap()
-                .to_offset(local_offset)
-        });
-
-        let format = time::format_description::parse_borrowed::<2>(
-            "[year]-[month]-[day] [hour]:[minute]:[second]",
-        )
-        .unwrap();
-
         let secret_str = secret.map(|s| {
             let bytes = s.as_bytes();
             if as_hex {
@@ -126,10 +122,11 @@ impl ItemOutput {
             .and_then(|attrs| attrs.remove(oo7::CONTENT_TYPE_ATTRIBUTE));
 
         Self {
+            path: None,
             label: label.to_string(),
             secret: secret_str,
-            created_at: created.map(|created| created.format(&format).unwrap()),
-            modified_at: modified.map(|modified| modified.format(&format).unwrap()),
+            created_at: created.map(format_time),
+            modified_at: modified.map(format_time),
             schema,
             content_type,
             attributes,
@@ -179,15 +176,18 @@ impl ItemOutput {
             Err(e) => Err(e),
         }?;
 
-        Ok(Self::new(
-            secret.as_ref(),
-            &item.label().await?,
-            attributes,
-            created,
-            modified,
-            is_locked,
-            as_hex,
-        ))
+        Ok(Self {
+            path: Some(item.path().to_string()),
+            ..Self::new(
+                secret.as_ref(),
+                &item.label().await?,
+                attributes,
+                created,
+                modified,
+                is_locked,
+                as_hex,
+            )
+        })
     }
 }
 
@@ -221,15 +221,553 @@ impl fmt::Display for ItemOutput {
     }
 }
 
+#[derive(Serialize)]
+struct CollectionOutput {
+    label: String,
+    path: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    created_at: String,
+    modified_at: String,
+    items: usize,
+    is_locked: bool,
+}
+
+impl CollectionOutput {
+    async fn new(
+        collection: &oo7::dbus::Collection,
+        known_aliases: &[(&str, Option<oo7::dbus::Collection>)],
+    ) -> Result<Self, Error> {
+        let aliases = known_aliases
+            .iter()
+            .filter(|(_, aliased)| {
+                aliased
+                    .as_ref()
+                    .is_some_and(|aliased| aliased.path() == collection.path())
+            })
+            .map(|(alias, _)| alias.to_string())
+            .collect();
+
+        Ok(Self {
+            label: collection.label().await?,
+            path: collection.path().to_string(),
+            aliases,
+            created_at: format_time(collection.created().await?),
+            modified_at: format_time(collection.modified().await?),
+            items: collection.items().await?.len(),
+            is_locked: collection.is_locked().await?,
+        })
+    }
+}
+
+impl fmt::Display for CollectionOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[{}]", self.label)?;
+        writeln!(f, "path = {}", self.path)?;
+        if !self.aliases.is_empty() {
+            writeln!(f, "aliases = {:?}", self.aliases)?;
+        }
+        writeln!(f, "created = {}", self.created_at)?;
+        writeln!(f, "modified = {}", self.modified_at)?;
+        writeln!(f, "items = {}", self.items)?;
+        writeln!(f, "locked = {}", self.is_locked)?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct KeyringOutput {
+    path: String,
+    version: String,
+    salt_size: usize,
+    kdf: String,
+    usage_count: u32,
+    modified_at: String,
+    items: Vec<LockedItemOutput>,
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct LockedItemOutput {
+    attribute_names: Vec<String>,
+}
+
+impl KeyringOutput {
+    async fn new(path: &Path) -> Result<Self, Error> {
+        // Loading a missing file would give back an empty keyring
+        if !path.exists() {
+            return Err(Error::Owned(format!("{} doesn't exist", path.display())));
+        }
+        let keyring = oo7::file::LockedKeyring::load(path).await?;
+
+        let (major, minor) = keyring.version().await;
+        let items = keyring
+            .items()
+            .await?
+            .iter()
+            .map(|item| {
+                let mut attribute_names = item
+                    .attribute_names()
+                    .map(ToOwned::to_owned)
+                    .collect::<Vec<_>>();
+                attribute_names.sort();
+                LockedItemOutput { attribute_names }
+            })
+            .collect();
+        let warnings = keyring
+            .key_strength()
+            .await
+            .err()
+            .map(|err| format!("Weak key derivation parameters: {err}"))
+            .into_iter()
+            .collect();
+
+        Ok(Self {
+            path: path.display().to_string(),
+            version: format!("{major}.{minor}"),
+            salt_size: keyring.salt_size().await,
+            kdf: keyring.kdf().await.to_string(),
+            usage_count: keyring.usage_count().await,
+            modified_at: format_time(keyring.modified_time().await),
+            items,
+            warnings,
+        })
+    }
+}
+
+impl fmt::Display for KeyringOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[{}]", self.path)?;
+        writeln!(f, "version = {}", self.version)?;
+        writeln!(f, "salt_size = {}", self.salt_size)?;
+        writeln!(f, "kdf = {}", self.kdf)?;
+        writeln!(f, "usage_count = {}", self.usage_count)?;
+        writeln!(f, "modified = {}", self.modified_at)?;
+        writeln!(f, "items = {}", self.items.len())?;
+        for (index, item) in self.items.iter().enumerate() {
+            writeln!(f, "item {index} attributes = {:?}", item.attribute_names)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct BrokenItemOutput {
+    index: usize,
+    attribute_names: Vec<String>,
+    reason: String,
+}
+
+impl BrokenItemOutput {
+    async fn from_keyring(keyring: &oo7::file::UnlockedKeyring) -> Result<Vec<Self>, Error> {
+        Ok(keyring
+            .all_items()
+            .await?
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let error = item.as_ref().err()?;
+                let mut attribute_names = error.attribute_names().to_vec();
+                attribute_names.sort();
+                Some(Self {
+                    index,
+                    attribute_names,
+                    reason: broken_item_reason(error.error()),
+                })
+            })
+            .collect())
+    }
+}
+
+fn broken_item_reason(error: &oo7::file::Error) -> String {
+    match error {
+        oo7::file::Error::MacError => {
+            "MAC mismatch, the item was encrypted with another secret or altered".to_owned()
+        }
+        oo7::file::Error::HashedAttributeMac(name) => {
+            format!("The hashed value of the attribute '{name}' doesn't match")
+        }
+        oo7::file::Error::Crypto(err) => format!("Decryption failed: {err}"),
+        err => err.to_string(),
+    }
+}
+
+impl fmt::Display for BrokenItemOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[item {}]", self.index)?;
+        writeln!(f, "attributes = {:?}", self.attribute_names)?;
+        writeln!(f, "reason = {}", self.reason)?;
+        Ok(())
+    }
+}
+
 enum Keyring {
-    File(oo7::file::UnlockedKeyring),
+    File(Box<oo7::file::UnlockedKeyring>),
     Collection(oo7::dbus::Collection),
 }
 
+impl Keyring {
+    /// Retrieve the secret of the first item matching `attributes`.
+    async fn lookup_secret(
+        &self,
+        attributes: &[(String, String)],
+    ) -> Result<Option<oo7::Secret>, Error> {
+        match self {
+            Keyring::Collection(collection) => {
+                if collection.is_locked().await? {
+                    collection.unlock(None).await?;
+                }
+                match collection.search_items(&attributes).await?.first() {
+                    Some(item) => Ok(Some(item.secret().await?)),
+                    None => Ok(None),
+                }
+            }
+            Keyring::File(keyring) => Ok(keyring
+                .lookup_item(&attributes)
+                .await?
+                .map(|item| item.secret())),
+        }
+    }
+}
+
 enum Output {
     None,
     SecretOnly(Vec<oo7::Secret>, bool), // secrets and hex flag
     Items(Vec<ItemOutput>, bool),       // items and json flag
+    Collections(Vec<CollectionOutput>, bool), // collections and json flag
+    Keyring(Box<KeyringOutput>, bool),  // keyring file metadata and json flag
+    BrokenItems(Vec<BrokenItemOutput>, bool), // broken items and json flag
+}
+
+impl Output {
+    fn print(self) -> Result<(), Error> {
+        match self {
+            Output::None => {}
+            Output::SecretOnly(secrets, hex) => {
+                for secret in secrets {
+                    print_secret_only(&secret, hex)?;
+                }
+            }
+            Output::Items(items, json) => {
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&items).unwrap());
+                } else {
+                    for item in items {
+                        print!("{}", item);
+                    }
+                }
+            }
+            Output::BrokenItems(items, json) => {
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&items).unwrap());
+                } else {
+                    for item in &items {
+                        print!("{item}");
+                    }
+                    println!("{} broken items", items.len());
+                }
+            }
+            Output::Keyring(keyring, json) => {
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&keyring).unwrap());
+                } else {
+                    print!("{}", keyring);
+                    for warning in &keyring.warnings {
+                        eprintln!("Warning: {warning}");
+                    }
+                }
+            }
+            Output::Collections(collections, json) => {
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&collections).unwrap());
+                } else {
+                    for collection in collections {
+                        print!("{}", collection);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Subcommand)]
+enum CollectionCommands {
+    #[command(name = "list", about = "List all the collections")]
+    List {
+        #[arg(long, help = "Format the output as json.")]
+        json: bool,
+    },
+
+    #[command(
+        name = "create",
+        about = "Create a new collection",
+        after_help = format!("{H_STYLE}Example:{H_STYLE:#}\n  {} collection create --alias work 'Work'", BINARY_NAME)
+    )]
+    Create {
+        #[arg(help = "Label of the collection")]
+        label: String,
+        #[arg(long, help = "Alias pointing to the new collection.")]
+        alias: Option<String>,
+    },
+
+    #[command(name = "delete", about = "Delete a collection and all its items")]
+    Delete {
+        #[arg(help = "Alias or label of the collection")]
+        collection: String,
+    },
+
+    #[command(name = "rename", about = "Change the label of a collection")]
+    Rename {
+        #[arg(help = "Alias or label of the collection")]
+        collection: String,
+        #[arg(help = "New label of the collection")]
+        label: String,
+    },
+
+    #[command(
+        name = "alias",
+        about = "Make an alias point to a collection",
+        after_help = format!("{H_STYLE}Example:{H_STYLE:#}\n  {} collection alias default 'Work'", BINARY_NAME)
+    )]
+    Alias {
+        #[arg(help = "The alias, for example default")]
+        alias: String,
+        #[arg(help = "Alias or label of the collection")]
+        collection: String,
+    },
+}
+
+impl CollectionCommands {
+    async fn execute(self, service: &Service) -> Result<Output, Error> {
+        match self {
+            CollectionCommands::List { json } => {
+                let mut known_aliases = Vec::new();
+                for alias in [Service::DEFAULT_COLLECTION, Service::SESSION_COLLECTION] {
+                    known_aliases.push((alias, service.with_alias(alias).await?));
+                }
+
+                let mut outputs = Vec::new();
+                for collection in service.collections().await? {
+                    outputs.push(CollectionOutput::new(&collection, &known_aliases).await?);
+                }
+                Ok(Output::Collections(outputs, json))
+            }
+            CollectionCommands::Create { label, alias } => {
+                service
+                    .create_collection(&label, alias.as_deref(), None)
+                    .await?;
+                Ok(Output::None)
+            }
+            CollectionCommands::Delete { collection } => {
+                find_collection(service, &collection)
+                    .await?
+                    .delete(None)
+                    .await?;
+                Ok(Output::None)
+            }
+            CollectionCommands::Rename { collection, label } => {
+                find_collection(service, &collection)
+                    .await?
+                    .set_label(&label)
+                    .await?;
+                Ok(Output::None)
+            }
+            CollectionCommands::Alias { alias, collection } => {
+                let collection = find_collection(service, &collection).await?;
+                service.set_alias(&alias, &collection).await?;
+                Ok(Output::None)
+            }
+        }
+    }
+}
+
+/// A change notified by the Secret Service.
+enum Change<'a> {
+    CollectionCreated(oo7::dbus::Collection),
+    CollectionChanged(oo7::dbus::Collection),
+    CollectionDeleted(OwnedObjectPath),
+    ItemCreated(&'a oo7::dbus::Collection, oo7::dbus::Item),
+    ItemChanged(&'a oo7::dbus::Collection, oo7::dbus::Item),
+    ItemDeleted(&'a oo7::dbus::Collection, OwnedObjectPath),
+}
+
+/// A change, printed as a single JSON line by the watch command.
+#[derive(Serialize)]
+struct WatchEvent {
+    event: &'static str,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attributes: Option<HashMap<String, String>>,
+}
+
+impl Change<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            Change::CollectionCreated(_) => "collection-created",
+            Change::CollectionChanged(_) => "collection-changed",
+            Change::CollectionDeleted(_) => "collection-deleted",
+            Change::ItemCreated(..) => "item-created",
+            Change::ItemChanged(..) => "item-changed",
+            Change::ItemDeleted(..) => "item-deleted",
+        }
+    }
+}
+
+impl WatchEvent {
+    // The object might be gone by the time we query it, so the label and the
+    // attributes are best effort.
+    async fn new(change: &Change<'_>) -> Self {
+        let event = change.name();
+        match change {
+            Change::CollectionCreated(collection) | Change::CollectionChanged(collection) => Self {
+                event,
+                path: collection.path().to_string(),
+                collection: None,
+                label: collection.label().await.ok(),
+                attributes: None,
+            },
+            Change::CollectionDeleted(path) => Self {
+                event,
+                path: path.to_string(),
+                collection: None,
+                label: None,
+                attributes: None,
+            },
+            Change::ItemCreated(collection, item) | Change::ItemChanged(collection, item) => Self {
+                event,
+                path: item.path().to_string(),
+                collection: Some(collection.path().to_string()),
+                label: item.label().await.ok(),
+                attributes: item.attributes().await.ok(),
+            },
+            Change::ItemDeleted(collection, path) => Self {
+                event,
+                path: path.to_string(),
+                collection: Some(collection.path().to_string()),
+                label: None,
+                attributes: None,
+            },
+        }
+    }
+
+    async fn print(change: &Change<'_>) {
+        let event = Self::new(change).await;
+        println!("{}", serde_json::to_string(&event).unwrap());
+    }
+}
+
+/// Print the changes of the collections, and of their items, until
+/// interrupted.
+///
+/// With `watch_new_collections`, the items of the collections created in the
+/// meantime are watched too.
+async fn watch(
+    service: &Service,
+    collections: Vec<oo7::dbus::Collection>,
+    watch_new_collections: bool,
+) -> Result<(), Error> {
+    let streams: Vec<LocalBoxStream<'_, Change<'_>>> = vec![
+        service
+            .receive_collection_created()
+            .await?
+            .map(Change::CollectionCreated)
+            .boxed_local(),
+        service
+            .receive_collection_changed()
+            .await?
+            .map(Change::CollectionChanged)
+            .boxed_local(),
+        service
+            .receive_collection_deleted()
+            .await?
+            .map(Change::CollectionDeleted)
+            .boxed_local(),
+    ];
+    let mut changes = stream::select_all(streams);
+    let mut item_changes = collections
+        .into_iter()
+        .map(|collection| watch_items(collection).boxed_local())
+        .collect::<FuturesUnordered<_>>();
+
+    loop {
+        tokio::select! {
+            change = changes.next() => {
+                let Some(change) = change else {
+                    break;
+                };
+                WatchEvent::print(&change).await;
+                if let Change::CollectionCreated(collection) = change
+                    && watch_new_collections
+                {
+                    item_changes.push(watch_items(collection).boxed_local());
+                }
+            }
+            Some(result) = item_changes.next() => result?,
+        }
+    }
+    Ok(())
+}
+
+/// Print the changes of the items of `collection`.
+async fn watch_items(collection: oo7::dbus::Collection) -> Result<(), Error> {
+    let collection = &collection;
+    let streams: Vec<LocalBoxStream<'_, Change<'_>>> = vec![
+        collection
+            .receive_item_created()
+            .await?
+            .map(move |item| Change::ItemCreated(collection, item))
+            .boxed_local(),
+        collection
+            .receive_item_changed()
+            .await?
+            .map(move |item| Change::ItemChanged(collection, item))
+            .boxed_local(),
+        collection
+            .receive_item_deleted()
+            .await?
+            .map(move |path| Change::ItemDeleted(collection, path))
+            .boxed_local(),
+    ];
+
+    let mut changes = stream::select_all(streams);
+    while let Some(change) = changes.next().await {
+        WatchEvent::print(&change).await;
+    }
+    Ok(())
+}
+
+/// Find a collection by its alias first, then by its label.
+async fn find_collection(service: &Service, name: &str) -> Result<oo7::dbus::Collection, Error> {
+    if let Some(collection) = service.with_alias(name).await? {
+        return Ok(collection);
+    }
+    service
+        .with_label(name)
+        .await?
+        .ok_or_else(|| Error::Owned(format!("Collection '{name}' not found")))
+}
+
+/// Find a collection by its alias, or by its object path as secret-tool
+/// accepts.
+async fn with_alias_or_path(service: &Service, name: &str) -> Result<oo7::dbus::Collection, Error> {
+    let collection = if name.starts_with('/') {
+        let mut found = None;
+        for collection in service.collections().await? {
+            if collection.path().as_str() == name {
+                found = Some(collection);
+                break;
+            }
+        }
+        found
+    } else {
+        service.with_alias(name).await?
+    };
+    collection.ok_or_else(|| Error::Owned(format!("Collection '{name}' not found")))
 }
 
 #[derive(Subcommand)]
@@ -310,6 +848,110 @@ enum Commands {
         attributes: Vec<(String, String)>,
     },
 
+    #[command(
+        name = "generate",
+        about = "Generate a random secret and store it",
+        after_help = format!("The secret is not printed unless --print is used.\n\n{H_STYLE}Examples:{H_STYLE:#}\n  {} generate --length 24 --classes lowercase,digits 'Database' service=db user=app\n  {0} generate --words 6 --print 'Disk encryption' device=sda", BINARY_NAME)
+    )]
+    Generate {
+        #[arg(help = "Description for the secret")]
+        label: String,
+        #[arg(
+            help = "List of attributes. This is a space-separated list of pairs key=value",
+            value_parser = parse_key_val::<String, String>,
+            required = true, num_args = 1
+        )]
+        attributes: Vec<(String, String)>,
+        #[arg(
+            long,
+            default_value_t = 32,
+            help = "Number of characters of the password."
+        )]
+        length: usize,
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            default_values_t = [generate::CharClass::Lowercase, generate::CharClass::Uppercase, generate::CharClass::Digits],
+            help = "Character classes of the password."
+        )]
+        classes: Vec<generate::CharClass>,
+        #[arg(
+            long,
+            conflicts_with_all = ["length", "classes"],
+            help = "Generate a passphrase of that many words instead of a password."
+        )]
+        words: Option<usize>,
+        #[arg(
+            long,
+            requires = "words",
+            help = "File with a word per line used for the passphrase. Defaults to /usr/share/dict/words."
+        )]
+        wordlist: Option<PathBuf>,
+        #[arg(
+            long,
+            default_value = "-",
+            requires = "words",
+            help = "Separator of the passphrase words."
+        )]
+        separator: String,
+        #[arg(long, help = "Print the generated secret.")]
+        print: bool,
+        #[arg(
+            long,
+            help = "Replace the secret of an existing item matching the attributes, keeping its label."
+        )]
+        replace: bool,
+    },
+
+    #[command(
+        name = "edit",
+        about = "Edit the label, attributes or secret of existing items",
+        after_help = format!("The new secret will be asked afterwards or read from stdin when --new-secret is used.\n\n{H_STYLE}Examples:{H_STYLE:#}\n  {} edit --label 'My Work Mail' smtp-port=1025\n  {0} edit --rename smtp-port=port --set host=smtp.example.org smtp-port=1025\n  systemd-ask-password -n | {0} edit --new-secret smtp-port=1025", BINARY_NAME)
+    )]
+    Edit {
+        #[arg(
+            help = "List of attributes. This is a space-separated list of pairs key=value",
+            value_parser = parse_key_val::<String, String>,
+            required = true,
+            num_args = 1
+        )]
+        attributes: Vec<(String, String)>,
+        #[arg(
+            short,
+            long,
+            help = "Whether to edit all the matches or only the first result"
+        )]
+        all: bool,
+        #[arg(long, help = "Set a new label.")]
+        label: Option<String>,
+        #[arg(
+            long = "set",
+            value_name = "KEY=VALUE",
+            help = "Add or update an attribute. Can be used multiple times.",
+            value_parser = parse_key_val::<String, String>
+        )]
+        set: Vec<(String, String)>,
+        #[arg(
+            long = "unset",
+            value_name = "KEY",
+            help = "Remove an attribute. Can be used multiple times."
+        )]
+        unset: Vec<String>,
+        #[arg(
+            long = "rename",
+            value_name = "OLD=NEW",
+            help = "Rename an attribute. Can be used multiple times.",
+            value_parser = parse_key_val::<String, String>
+        )]
+        rename: Vec<(String, String)>,
+        #[arg(long, help = "Replace the secret.")]
+        new_secret: bool,
+    },
+
+    #[command(subcommand, name = "collection", about = "Manage the collections")]
+    Collection(CollectionCommands),
+
     #[command(name = "list", about = "List all the items in the keyring")]
     List {
         #[arg(long, help = "Print the secret in hexadecimal.")]
@@ -324,13 +966,158 @@ enum Commands {
     #[command(name = "unlock", about = "Unlock the keyring")]
     Unlock,
 
-    #[command(name = "repair", about = "Repair the keyring")]
-    Repair,
+    #[command(
+        name = "verify",
+        about = "List the items of the keyring that cannot be decrypted",
+        after_help = format!("Nothing is modified, use repair to delete the broken items.\n\n{H_STYLE}Example:{H_STYLE:#}\n  {} --keyring login.keyring --secret-file password verify", BINARY_NAME)
+    )]
+    Verify {
+        #[arg(long, help = "Format the output as json.")]
+        json: bool,
+    },
+
+    #[command(
+        name = "repair",
+        about = "Repair the keyring",
+        after_help = format!("The items that cannot be decrypted are deleted.\n\n{H_STYLE}Examples:{H_STYLE:#}\n  {} --keyring login.keyring repair --dry-run\n  {0} --keyring login.keyring repair --export-broken broken.keyring", BINARY_NAME)
+    )]
+    Repair {
+        #[arg(long, help = "Only list the items that would be deleted.")]
+        dry_run: bool,
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with = "dry_run",
+            help = "Save the broken items to a new keyring file before deleting them. They can be recovered later by repairing that file with the secret they were created with."
+        )]
+        export_broken: Option<PathBuf>,
+    },
+
+    #[command(
+        name = "change-password",
+        about = "Change the password of the keyring",
+        after_help = format!("The current and the new password are read from the terminal, unless --prompt is used.\n\n{H_STYLE}Examples:{H_STYLE:#}\n  {} change-password\n  {0} --keyring login.keyring --secret password change-password", BINARY_NAME)
+    )]
+    ChangePassword {
+        #[arg(
+            long,
+            help = "Let the Secret Service prompt for the new password. Only supported for collections."
+        )]
+        prompt: bool,
+    },
+
+    #[command(
+        name = "exec",
+        about = "Run a command with secrets in its environment",
+        after_help = format!("Each mapping is VAR=key=value,key2=value2, VAR is set to the secret of the item matching the attributes. With --file, VAR is set to the path of a file holding the secret, in a private directory of XDG_RUNTIME_DIR removed once the command exits.\n\n{H_STYLE}Example:{H_STYLE:#}\n  {} exec --env DB_PASS=service=db,user=app -- psql -h localhost", BINARY_NAME)
+    )]
+    Exec {
+        #[arg(
+            long = "env",
+            value_name = "VAR=ATTRIBUTES",
+            help = "Set VAR to the secret of the item matching the attributes."
+        )]
+        variables: Vec<exec::Mapping>,
+        #[arg(
+            long = "file",
+            value_name = "VAR=ATTRIBUTES",
+            help = "Set VAR to the path of a file holding the secret of the item matching the attributes."
+        )]
+        files: Vec<exec::Mapping>,
+        #[arg(
+            help = "The command to run and its arguments",
+            required = true,
+            last = true
+        )]
+        command: Vec<String>,
+    },
+
+    #[command(
+        name = "watch",
+        about = "Print a JSON line for every change of the items and the collections",
+        after_help = format!("The items of all the collections are watched, unless --collection is used.\n\n{H_STYLE}Example:{H_STYLE:#}\n  {} --collection login watch", BINARY_NAME)
+    )]
+    Watch,
+
+    #[command(
+        name = "inspect",
+        about = "Show the metadata of a keyring file",
+        after_help = format!("The keyring secret is not needed.\n\n{H_STYLE}Example:{H_STYLE:#}\n  {} inspect ~/.local/share/keyrings/login.keyring", BINARY_NAME)
+    )]
+    Inspect {
+        #[arg(help = "Path of the keyring file")]
+        path: PathBuf,
+        #[arg(long, help = "Format the output as json.")]
+        json: bool,
+    },
+
+    #[command(
+        name = "export",
+        about = "Export all the items of the keyring to an archive",
+        after_help = format!("The archive is a JSON document, or a keyring file protected by a passphrase when --encrypt is used.\n\n{H_STYLE}Examples:{H_STYLE:#}\n  {} export backup.json\n  {0} export --encrypt backup.keyring", BINARY_NAME)
+    )]
+    Export {
+        #[arg(help = "Path of the archive to create")]
+        path: PathBuf,
+        #[arg(long, help = "Protect the archive with a passphrase.")]
+        encrypt: bool,
+    },
+
+    #[command(
+        name = "import",
+        about = "Import the items of an archive into the keyring",
+        after_help = format!("The passphrase will be asked if the archive is encrypted.\n\n{H_STYLE}Example:{H_STYLE:#}\n  {} --collection login import backup.json", BINARY_NAME)
+    )]
+    Import {
+        #[arg(help = "Path of the archive to import")]
+        path: PathBuf,
+        #[arg(
+            long,
+            help = "Replace the existing items that have the same attributes."
+        )]
+        replace: bool,
+    },
 }
 
 impl Commands {
     async fn execute(self, args: Arguments) -> Result<(), Error> {
+        self.run(args, None).await?.print()
+    }
+
+    /// Run the command, `item_secret` being the secret to store with
+    /// [`Commands::Store`]. It is read from the terminal when missing.
+    async fn run(self, args: Arguments, item_secret: Option<oo7::Secret>) -> Result<Output, Error> {
+        // Inspecting a keyring file doesn't involve the Secret Service
+        if let Commands::Inspect { path, json } = &self {
+            return Ok(Output::Keyring(
+                Box::new(KeyringOutput::new(path).await?),
+                *json,
+            ));
+        }
         let service = Service::new().await?;
+        let command = match self {
+            Commands::Collection(command) => {
+                if args.keyring.is_some() || args.app_id.is_some() {
+                    return Err(Error::new(
+                        "Collections can only be managed on the Secret Service.",
+                    ));
+                }
+                return command.execute(&service).await;
+            }
+            Commands::Watch => {
+                if args.keyring.is_some() || args.app_id.is_some() {
+                    return Err(Error::new("Only the Secret Service can be watched."));
+                }
+                let collections = if let Some(collection) = &args.collection {
+                    vec![find_collection(&service, collection).await?]
+                } else {
+                    service.collections().await?
+                };
+                watch(&service, collections, args.collection.is_none()).await?;
+                return Ok(Output::None);
+            }
+            command => command,
+        };
         if args.app_id.is_some() && args.keyring.is_some() {
             return Err(Error::new(
                 "Only one of application ID or keyring can be specified at a time.",
@@ -361,9 +1148,13 @@ impl Commands {
                 path
             });
             (Some(secret), path)
-        } else if let Some(keyring) = args.keyring {
-            (args.secret, Some(keyring))
-        } else if let Some(secret) = args.secret {
+        } else if let Some(keyring) = args.keyring.clone() {
+            let secret = match args.explicit_secret()? {
+                Some(secret) => Some(secret),
+                None => keyring_secret_fallback()?,
+            };
+            (secret, Some(keyring))
+        } else if let Some(secret) = args.explicit_secret()? {
             (
                 Some(secret),
                 data_dir().map(|mut path| {
@@ -375,22 +1166,30 @@ impl Commands {
             (None, None)
         };
 
-        let keyring = match (path, secret) {
-            (Some(path), Some(secret)) => unsafe {
-                Keyring::File(oo7::file::UnlockedKeyring::load_unchecked(path, secret).await?)
+        let keyring = match (path, secret.clone()) {
+            // The items that cannot be decrypted are expected when verifying or
+            // repairing, a mistyped secret is refused otherwise
+            (Some(path), Some(secret))
+                if matches!(command, Commands::Verify { .. } | Commands::Repair { .. }) =>
+            unsafe {
+                Keyring::File(Box::new(
+                    oo7::file::UnlockedKeyring::load_unchecked(path, secret).await?,
+                ))
             },
+            (Some(path), Some(secret)) => Keyring::File(Box::new(
+                oo7::file::UnlockedKeyring::load(path, secret).await?,
+            )),
             (Some(_), None) => {
-                return Err(Error::new("A keyring requires a secret."));
+                return Err(Error::new(
+                    "A keyring requires a secret, use --secret-file, --secret-fd, --secret-credential or OO7_KEYRING_SECRET.",
+                ));
             }
             (None, Some(_)) => {
                 return Err(Error::new("A secret requires a keyring."));
             }
             _ => {
-                let collection = if let Some(alias) = &args.collection {
-                    service
-                        .with_alias(alias)
-                        .await?
-                        .ok_or_else(|| Error::Owned(format!("Collection '{alias}' not found")))?
+                let collection = if let Some(name) = &args.collection {
+                    with_alias_or_path(&service, name).await?
                 } else {
                     service.default_collection().await?
                 };
@@ -398,7 +1197,7 @@ impl Commands {
             }
         };
 
-        let output = match self {
+        let output = match command {
             Commands::Delete { attributes } => {
                 match keyring {
                     Keyring::Collection(collection) => {
@@ -499,17 +1298,9 @@ impl Commands {
                 }
             },
             Commands::Store { label, attributes } => {
-                let mut stdin = std::io::stdin().lock();
-                let secret = if stdin.is_terminal() {
-                    print!("Type a secret: ");
-                    std::io::stdout()
-                        .flush()
-                        .map_err(|_| Error::new("Could not flush stdout"))?;
-                    rpassword::read_password().map_err(|_| Error::new("Can't read password"))?
-                } else {
-                    let mut secret = String::new();
-                    stdin.read_line(&mut secret)?;
-                    secret
+                let secret = match item_secret {
+                    Some(secret) => secret,
+                    None => read_secret("Type a secret: ")?.into(),
                 };
 
                 match keyring {
@@ -526,6 +1317,151 @@ impl Commands {
                 }
                 Output::None
             }
+            Commands::Generate {
+                label,
+                attributes,
+                length,
+                classes,
+                words,
+                wordlist,
+                separator,
+                print,
+                replace,
+            } => {
+                let secret = oo7::Secret::text(match words {
+                    Some(n_words) => {
+                        generate::passphrase(n_words, wordlist.as_deref(), &separator)?
+                    }
+                    None => generate::password(length, &classes)?,
+                });
+                let exists_error = || {
+                    Error::new(
+                        "An item with the same attributes exists, use --replace to replace its secret.",
+                    )
+                };
+
+                match keyring {
+                    Keyring::File(keyring) => match keyring.lookup_item_index(&attributes).await? {
+                        Some(index) if replace => {
+                            let mut item = keyring
+                                .lookup_item(&attributes)
+                                .await?
+                                .ok_or_else(|| Error::new("No matching item found."))?;
+                            item.set_secret(secret.clone());
+                            keyring.replace_item_index(index, &item).await?;
+                        }
+                        Some(_) => return Err(exists_error()),
+                        None => {
+                            keyring
+                                .create_item(&label, &attributes, secret.clone(), false)
+                                .await?;
+                        }
+                    },
+                    Keyring::Collection(collection) => {
+                        if collection.is_locked().await? {
+                            collection.unlock(None).await?;
+                        }
+                        match collection.search_items(&attributes).await?.first() {
+                            Some(item) if replace => item.set_secret(secret.clone()).await?,
+                            Some(_) => return Err(exists_error()),
+                            None => {
+                                collection
+                                    .create_item(&label, &attributes, secret.clone(), false, None)
+                                    .await?;
+                            }
+                        }
+                    }
+                }
+                if print {
+                    Output::SecretOnly(vec![secret], false)
+                } else {
+                    Output::None
+                }
+            }
+            Commands::Edit {
+                attributes,
+                all,
+                label,
+                set,
+                unset,
+                rename,
+                new_secret,
+            } => {
+                let edit = AttributesEdit { set, unset, rename };
+                if label.is_none() && edit.is_empty() && !new_secret {
+                    return Err(Error::new(
+                        "Nothing to edit, use at least one of --label, --set, --unset, --rename or --new-secret.",
+                    ));
+                }
+                let secret = if new_secret {
+                    Some(oo7::Secret::from(read_secret("Type the new secret: ")?))
+                } else {
+                    None
+                };
+
+                let n_edited = match keyring {
+                    Keyring::Collection(collection) => {
+                        let items = collection.search_items(&attributes).await?;
+                        let items_to_edit: Vec<_> = if all {
+                            items.iter().collect()
+                        } else {
+                            items.first().into_iter().collect()
+                        };
+
+                        for item in &items_to_edit {
+                            if let Some(label) = &label {
+                                item.set_label(label).await?;
+                            }
+                            if !edit.is_empty() {
+                                let attributes = edit.apply(item.attributes().await?)?;
+                                item.set_attributes(&attributes).await?;
+                            }
+                            if let Some(secret) = &secret {
+                                item.set_secret(secret.clone()).await?;
+                            }
+                        }
+                        items_to_edit.len()
+                    }
+                    Keyring::File(keyring) => {
+                        let mut n_edited = 0;
+                        // The index of all_items() is the one expected by replace_item_index()
+                        for (index, item) in keyring.all_items().await?.into_iter().enumerate() {
+                            let Ok(mut item) = item else {
+                                continue;
+                            };
+                            let matches = attributes.iter().all(|(key, value)| {
+                                item.attributes().get(key).map(String::as_str) == Some(value)
+                            });
+                            if !matches {
+                                continue;
+                            }
+
+                            if let Some(label) = &label {
+                                item.set_label(label);
+                            }
+                            if !edit.is_empty() {
+                                let attributes = edit.apply(item.attributes().clone())?;
+                                item.set_attributes(&attributes);
+                            }
+                            if let Some(secret) = &secret {
+                                item.set_secret(secret.clone());
+                            }
+                            keyring.replace_item_index(index, &item).await?;
+
+                            n_edited += 1;
+                            if !all {
+                                break;
+                            }
+                        }
+                        n_edited
+                    }
+                };
+
+                if n_edited == 0 {
+                    return Err(Error::new("No matching item found."));
+                }
+                Output::None
+            }
             Commands::List { hex, json } => {
                 let items = match keyring {
                     Keyring::File(keyring) => {
@@ -575,11 +1511,43 @@ impl Commands {
                 }
                 Output::None
             }
-            Commands::Repair => {
+            Commands::Verify { json } => match keyring {
+                Keyring::File(keyring) => {
+                    Output::BrokenItems(BrokenItemOutput::from_keyring(&keyring).await?, json)
+                }
+                Keyring::Collection(_) => {
+                    return Err(Error::new("Only a keyring file can be verified."));
+                }
+            },
+            Commands::Repair {
+                dry_run,
+                export_broken,
+            } => {
                 match keyring {
                     Keyring::File(keyring) => {
-                        let deleted_items = keyring.delete_broken_items().await?;
-                        println!("{deleted_items} broken items were deleted");
+                        if let Some(path) = export_broken {
+                            if path.exists() {
+                                return Err(Error::Owned(format!(
+                                    "{} already exists",
+                                    path.display()
+                                )));
+                            }
+                            let exported_items = keyring.dump_broken_items(&path).await?;
+                            println!(
+                                "{exported_items} broken items were exported to {}",
+                                path.display()
+                            );
+                        }
+                        if dry_run {
+                            let broken_items = BrokenItemOutput::from_keyring(&keyring).await?;
+                            for item in &broken_items {
+                                print!("{item}");
+                            }
+                            println!("{} broken items would be deleted", broken_items.len());
+                        } else {
+                            let deleted_items = keyring.delete_broken_items().await?;
+                            println!("{deleted_items} broken items were deleted");
+                        }
                     }
                     Keyring::Collection(_) => {
                         return Err(Error::new("Only a keyring file can be repaired."));
@@ -587,28 +1555,141 @@ impl Commands {
                 }
                 Output::None
             }
-        };
+            Commands::ChangePassword { prompt } => {
+                match keyring {
+                    Keyring::File(keyring) => {
+                        if prompt {
+                            return Err(Error::new("Prompting is only supported for collections."));
+                        }
+                        // The keyring was loaded without validating the secret
+                        let current = secret.expect("A keyring file requires a secret");
+                        if !keyring.validate_secret(&current).await? {
+                            return Err(Error::new("The keyring secret is incorrect."));
+                        }
+                        let new = read_passphrase("New password", true)?;
+                        keyring.change_secret(new).await?;
+                    }
+                    Keyring::Collection(collection) => {
+                        if prompt {
+                            if collection.is_locked().await? {
+                                collection.unlock(None).await?;
+                            }
+                            collection.change_secret_with_prompt(None).await?;
+                        } else {
+                            let current = read_passphrase("Current password", false)?;
+                            let new = read_passphrase("New password", true)?;
+                            collection.change_secret(current, new).await?;
+                        }
+                    }
+                }
+                println!("Password changed");
+                Output::None
+            }
+            Commands::Exec {
+                variables,
+                files,
+                command,
+            } => {
+                let mut resolved = Vec::with_capacity(variables.len() + files.len());
+                for mapping in variables.iter().chain(&files) {
+                    let secret = keyring
+                        .lookup_secret(&mapping.attributes)
+                        .await?
+                        .ok_or_else(|| {
+                            Error::Owned(format!("No item found for {}", mapping.variable))
+                        })?;
+                    resolved.push((mapping.variable.clone(), secret));
+                }
+                let files = resolved.split_off(variables.len());
 
-        // Unified output printing
-        match output {
-            Output::None => {}
-            Output::SecretOnly(secrets, hex) => {
-                for secret in secrets {
-                    print_secret_only(&secret, hex)?;
+                let code = exec::run(&command, resolved, files)?;
+                std::process::exit(code);
+            }
+            Commands::Export { path, encrypt } => {
+                let items = match keyring {
+                    Keyring::File(keyring) => {
+                        let (items, broken_items): (Vec<_>, Vec<_>) = keyring
+                            .all_items()
+                            .await?
+                            .into_iter()
+                            .partition(Result::is_ok);
+                        if !broken_items.is_empty() {
+                            eprintln!(
+                                "Warning: {} items that cannot be decrypted were skipped, use `verify` to list them and `repair --export-broken` to save them",
+                                broken_items.len()
+                            );
+                        }
+                        items
+                            .into_iter()
+                            .flatten()
+                            .map(|item| ArchiveItem::from_file_item(&item))
+                            .collect::<Vec<_>>()
+                    }
+                    Keyring::Collection(collection) => {
+                        if collection.is_locked().await? {
+                            collection.unlock(None).await?;
+                        }
+                        let mut items = Vec::new();
+                        for item in collection.items().await? {
+                            items.push(ArchiveItem::from_dbus_item(&item).await?);
+                        }
+                        items
+                    }
+                };
+                let n_items = items.len();
+                let archive = Archive::new(items);
+                if encrypt {
+                    let passphrase = read_passphrase("Passphrase", true)?;
+                    archive.write_encrypted(&path, passphrase).await?;
+                } else {
+                    archive.write(&path)?;
                 }
+                println!("{n_items} items exported to {}", path.display());
+                Output::None
             }
-            Output::Items(items, json) => {
-                if json {
-                    println!("{}", serde_json::to_string_pretty(&items).unwrap());
+            Commands::Import { path, replace } => {
+                let archive = if Archive::is_encrypted(&path)? {
+                    let passphrase = read_passphrase("Passphrase", false)?;
+                    Archive::read_encrypted(&path, passphrase).await?
                 } else {
-                    for item in items {
-                        print!("{}", item);
+                    Archive::read(&path)?
+                };
+                let n_items = archive.items.len();
+                match keyring {
+                    Keyring::File(keyring) => {
+                        let items = archive
+                            .items
+                            .iter()
+                            .map(ArchiveItem::to_file_item)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        keyring.insert_items(items, replace).await?;
+                    }
+                    Keyring::Collection(collection) => {
+                        if collection.is_locked().await? {
+                            collection.unlock(None).await?;
+                        }
+                        for item in archive.items {
+                            collection
+                                .create_item(
+                                    &item.label,
+                                    &item.attributes,
+                                    item.secret()?,
+                                    replace,
+                                    None,
+                                )
+                                .await?;
+                        }
                     }
                 }
+                println!("{n_items} items imported from {}", path.display());
+                Output::None
             }
-        }
+            Commands::Collection(_) | Commands::Watch | Commands::Inspect { .. } => {
+                unreachable!()
+            }
+        };
 
-        Ok(())
+        Ok(output)
     }
 }
 
@@ -647,6 +1728,31 @@ struct Arguments {
         help = "Specify the keyring secret. The default collection will be used if not specified"
     )]
     secret: Option<oo7::Secret>,
+    #[arg(
+        name = "secret-fd",
+        long,
+        global = true,
+        conflicts_with_all = ["secret", "secret-file", "secret-credential"],
+        value_parser = clap::value_parser!(i32).range(0..),
+        help = "Read the keyring secret from a file descriptor, like --secret"
+    )]
+    secret_fd: Option<i32>,
+    #[arg(
+        name = "secret-file",
+        long,
+        global = true,
+        conflicts_with_all = ["secret", "secret-credential"],
+        help = "Read the keyring secret from a file, like --secret"
+    )]
+    secret_file: Option<PathBuf>,
+    #[arg(
+        name = "secret-credential",
+        long,
+        global = true,
+        conflicts_with = "secret",
+        help = "Read the keyring secret from a systemd credential of $CREDENTIALS_DIRECTORY, like --secret"
+    )]
+    secret_credential: Option<String>,
     #[arg(
         name = "app-id",
         long,
@@ -656,8 +1762,80 @@ struct Arguments {
     app_id: Option<oo7::ashpd::AppID>,
 }
 
+impl Arguments {
+    /// The keyring secret passed with --secret or read from the source given
+    /// by --secret-fd, --secret-file or --secret-credential.
+    fn explicit_secret(&self) -> Result<Option<oo7::Secret>, Error> {
+        let content = if let Some(secret) = &self.secret {
+            return Ok(Some(secret.clone()));
+        } else if let Some(fd) = self.secret_fd {
+            use std::{io::Read, os::fd::BorrowedFd};
+
+            let fd_error = |err: std::io::Error| {
+                Error::Owned(format!("Failed to read file descriptor {fd}: {err}"))
+            };
+            // SAFETY: the file descriptor is only borrowed while checking that
+            // it is open, nothing else closes it in the meantime.
+            let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+            rustix::io::fcntl_getfd(borrowed).map_err(|err| fd_error(err.into()))?;
+            // Read from a duplicate, leaving the file descriptor of the user open
+            let mut file = std::fs::File::from(borrowed.try_clone_to_owned().map_err(fd_error)?);
+            let mut content = Vec::new();
+            file.read_to_end(&mut content).map_err(fd_error)?;
+            content
+        } else if let Some(path) = &self.secret_file {
+            std::fs::read(path)
+                .map_err(|err| Error::Owned(format!("Failed to read {}: {err}", path.display())))?
+        } else if let Some(name) = &self.secret_credential {
+            let directory = std::env::var_os("CREDENTIALS_DIRECTORY")
+                .filter(|directory| !directory.is_empty())
+                .ok_or_else(|| Error::new("CREDENTIALS_DIRECTORY is not set"))?;
+            let path = Path::new(&directory).join(name);
+            std::fs::read(&path)
+                .map_err(|err| Error::Owned(format!("Failed to read credential {name}: {err}")))?
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(secret_from_bytes(content)))
+    }
+}
+
+/// The keyring secret used with --keyring when no other source is given.
+const SECRET_ENV: &str = "OO7_KEYRING_SECRET";
+
+/// Ask for the keyring secret if none was given and we can prompt for it.
+fn keyring_secret_fallback() -> Result<Option<oo7::Secret>, Error> {
+    if let Some(secret) = std::env::var_os(SECRET_ENV) {
+        use std::os::unix::ffi::OsStringExt;
+
+        return Ok(Some(secret_from_bytes(secret.into_vec())));
+    }
+    if std::io::stdin().is_terminal() {
+        let secret = rpassword::prompt_password("Keyring password: ")
+            .map_err(|_| Error::new("Can't read the keyring password"))?;
+        return Ok(Some(oo7::Secret::text(secret)));
+    }
+    Ok(None)
+}
+
+/// Turn the content of a file into a secret, ignoring a trailing new line.
+fn secret_from_bytes(mut content: Vec<u8>) -> oo7::Secret {
+    if content.last() == Some(&b'\n') {
+        content.pop();
+    }
+    match String::from_utf8(content) {
+        Ok(text) => oo7::Secret::text(text),
+        Err(err) => oo7::Secret::blob(err.into_bytes()),
+    }
+}
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Error> {
+    if secret_tool::is_invoked() {
+        let code = secret_tool::run(std::env::args().skip(1)).await;
+        std::process::exit(code);
+    }
     let cli = Cli::parse();
     cli.command.execute(cli.args).await
 }
@@ -694,6 +1872,86 @@ fn print_secret_only(secret: &oo7::Secret, as_hex: bool) -> Result<(), Error> {
     Ok(())
 }
 
+fn format_time(time: Duration) -> String {
+    let local_offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+    let format = time::format_description::parse_borrowed::<2>(
+        "[year]-[month]-[day] [hour]:[minute]:[second]",
+    )
+    .unwrap();
+
+    OffsetDateTime::from_unix_timestamp(time.as_secs() as i64)
+        .unwrap()
+        .to_offset(local_offset)
+        .format(&format)
+        .unwrap()
+}
+
+/// Changes to apply to the attributes of an item.
+struct AttributesEdit {
+    set: Vec<(String, String)>,
+    unset: Vec<String>,
+    rename: Vec<(String, String)>,
+}
+
+impl AttributesEdit {
+    fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.unset.is_empty() && self.rename.is_empty()
+    }
+
+    /// Renames, then removes and finally sets the attributes.
+    fn apply(
+        &self,
+        mut attributes: HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, Error> {
+        for (old, new) in &self.rename {
+            let value = attributes
+                .remove(old)
+                .ok_or_else(|| Error::Owned(format!("Attribute '{old}' not found")))?;
+            attributes.insert(new.clone(), value);
+        }
+        for key in &self.unset {
+            attributes.remove(key);
+        }
+        for (key, value) in &self.set {
+            attributes.insert(key.clone(), value.clone());
+        }
+        Ok(attributes)
+    }
+}
+
+/// Read a secret from stdin, asking for it if stdin is a terminal.
+fn read_secret(prompt: &str) -> Result<String, Error> {
+    let mut stdin = std::io::stdin().lock();
+    if stdin.is_terminal() {
+        print!("{prompt}");
+        std::io::stdout()
+            .flush()
+            .map_err(|_| Error::new("Could not flush stdout"))?;
+        rpassword::read_password().map_err(|_| Error::new("Can't read password"))
+    } else {
+        let mut secret = String::new();
+        stdin.read_line(&mut secret)?;
+        Ok(secret)
+    }
+}
+
+/// Ask the user for a passphrase, twice if it is a new one.
+///
+/// `name` is used in the prompts, for example `Passphrase` or `New password`.
+fn read_passphrase(name: &str, confirm: bool) -> Result<oo7::Secret, Error> {
+    let lowercase_name = name.to_lowercase();
+    let passphrase = rpassword::prompt_password(format!("{name}: "))
+        .map_err(|_| Error::Owned(format!("Can't read {lowercase_name}")))?;
+    if confirm {
+        let confirmation = rpassword::prompt_password(format!("Confirm {lowercase_name}: "))
+            .map_err(|_| Error::Owned(format!("Can't read {lowercase_name}")))?;
+        if passphrase != confirmation {
+            return Err(Error::Owned(format!("{name}s do not match")));
+        }
+    }
+    Ok(oo7::Secret::text(passphrase))
+}
+
 // Copy from /client/src/file/api/mod.rs
 fn data_dir() -> Option<PathBuf> {
     std::env::var_os("XDG_DATA_HOME")
@@ -708,3 +1966,34 @@ fn home() -> Option<PathBuf> {
         .and_then(|h| if h.is_empty() { None } else { Some(h) })
         .map(PathBuf::from)
 }
+
+#[cfg(test)]
+mod tests {
+    use oo7_test_daemon::{Collection, TestDaemon};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn collection_alias_or_path() {
+        let daemon = TestDaemon::builder()
+            .collection(Collection::new("Work", "work-password"))
+            .build()
+            .await
+            .unwrap();
+        let service = daemon.service().await.unwrap();
+
+        let work = with_alias_or_path(&service, "work").await.unwrap();
+        assert_eq!(work.label().await.unwrap(), "Work");
+
+        let path = work.path().to_string();
+        let by_path = with_alias_or_path(&service, &path).await.unwrap();
+        assert_eq!(by_path.path(), work.path());
+
+        assert!(with_alias_or_path(&service, "unknown").await.is_err());
+        assert!(
+            with_alias_or_path(&service, "/org/freedesktop/secrets/collection/unknown")
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,381 @@
+//! A `secret-tool` compatible front end.
+//!
+//! When `oo7-cli` is invoked as `secret-tool`, through a symbolic link for
+//! example, it accepts libsecret's `secret-tool` syntax and mimics its output
+//! format and exit codes. Each action is mapped onto the regular commands.
+
+use std::{
+    ffi::OsStr,
+    io::{IsTerminal, Read, Write},
+    path::Path,
+};
+
+use crate::{Arguments, Commands, Error, ItemOutput, Output};
+
+const NAME: &str = "secret-tool";
+
+const USAGE: &str = "usage: secret-tool store --label='label' attribute value ...
+       secret-tool lookup attribute value ...
+       secret-tool clear attribute value ...
+       secret-tool search [--all] [--unlock] attribute value ...
+       secret-tool lock --collection='collection'";
+
+/// Exit code of invalid invocations.
+const EXIT_USAGE: i32 = 2;
+
+/// Whether the binary was invoked as `secret-tool`.
+pub fn is_invoked() -> bool {
+    std::env::args_os()
+        .next()
+        .is_some_and(|arg0| Path::new(&arg0).file_name() == Some(OsStr::new(NAME)))
+}
+
+/// Run the `secret-tool` command line and return its exit code.
+pub async fn run(args: impl IntoIterator<Item = String>) -> i32 {
+    let mut args = args.into_iter();
+    let Some(action) = args.next() else {
+        return usage(None);
+    };
+    let invocation = match Invocation::parse(&action, args) {
+        Ok(invocation) => invocation,
+        Err(message) => return usage(message),
+    };
+
+    match invocation.execute().await {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("{NAME}: {err:?}");
+            1
+        }
+    }
+}
+
+fn usage(message: Option<String>) -> i32 {
+    if let Some(message) = message {
+        eprintln!("{NAME}: {message}");
+    }
+    eprintln!("{USAGE}");
+    EXIT_USAGE
+}
+
+#[derive(Default)]
+struct Invocation {
+    action: String,
+    label: Option<String>,
+    collection: Option<String>,
+    all: bool,
+    unlock: bool,
+    attributes: Vec<(String, String)>,
+}
+
+impl Invocation {
+    fn parse(action: &str, mut args: impl Iterator<Item = String>) -> Result<Self, Option<String>> {
+        let mut invocation = Self {
+            action: action.to_owned(),
+            ..Default::default()
+        };
+        let (options, attributes): (&[&str], bool) = match action {
+            "store" => (&["label", "collection"], true),
+            "lookup" | "clear" => (&[], true),
+            "search" => (&["all", "unlock"], true),
+            "lock" => (&["collection"], false),
+            _ => return Err(None),
+        };
+
+        let mut positionals = Vec::new();
+        while let Some(arg) = args.next() {
+            let (name, value) = match arg.as_str() {
+                "--" => {
+                    positionals.extend(args.by_ref());
+                    break;
+                }
+                "-l" => ("label", None),
+                "-c" => ("collection", None),
+                "-a" => ("all", None),
+                "-u" => ("unlock", None),
+                long if long.starts_with("--") => match long[2..].split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_owned())),
+                    None => (&long[2..], None),
+                },
+                _ => {
+                    positionals.push(arg);
+                    continue;
+                }
+            };
+            if !options.contains(&name) {
+                return Err(Some(format!("Unknown option {arg}")));
+            }
+            match name {
+                "all" => invocation.all = true,
+                "unlock" => invocation.unlock = true,
+                _ => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or_else(|| Some(format!("Missing argument for --{name}")))?;
+                    if name == "label" {
+                        invocation.label = Some(value);
+                    } else {
+                        invocation.collection = Some(value);
+                    }
+                }
+            }
+        }
+
+        if attributes {
+            if positionals.is_empty() || positionals.len() % 2 != 0 {
+                return Err(Some("must specify attribute and value pairs".to_owned()));
+            }
+            let mut positionals = positionals.into_iter();
+            while let (Some(key), Some(value)) = (positionals.next(), positionals.next()) {
+                invocation.attributes.push((key, value));
+            }
+        } else if !positionals.is_empty() {
+            return Err(None);
+        }
+
+        match action {
+            "store" if invocation.label.is_none() => {
+                Err(Some("must specify a label for the new item".to_owned()))
+            }
+            "lock" if invocation.collection.is_none() => {
+                Err(Some("must specify a collection".to_owned()))
+            }
+            _ => Ok(invocation),
+        }
+    }
+
+    fn arguments(&self) -> Arguments {
+        Arguments {
+            collection: self.collection.clone(),
+            keyring: None,
+            secret: None,
+            secret_fd: None,
+            secret_file: None,
+            secret_credential: None,
+            app_id: None,
+        }
+    }
+
+    async fn execute(self) -> Result<i32, Error> {
+        let attributes = self.attributes.clone();
+        match self.action.as_str() {
+            "store" => {
+                let command = Commands::Store {
+                    label: self.label.clone().unwrap_or_default(),
+                    attributes,
+                };
+                command
+                    .run(self.arguments(), Some(read_password()?))
+                    .await?;
+            }
+            "lookup" => {
+                // Like secret-tool, unlock the collection to read the secret
+                Commands::Unlock.run(self.arguments(), None).await?;
+                let command = Commands::Lookup {
+                    attributes,
+                    secret_only: true,
+                    hex: false,
+                    json: false,
+                };
+                match command.run(self.arguments(), None).await? {
+                    Output::SecretOnly(secrets, _) if !secrets.is_empty() => {
+                        write_password(&secrets[0])?;
+                    }
+                    _ => return Ok(1),
+                }
+            }
+            "clear" => {
+                Commands::Delete { attributes }
+                    .run(self.arguments(), None)
+                    .await?;
+            }
+            "search" => {
+                if self.unlock {
+                    Commands::Unlock.run(self.arguments(), None).await?;
+                }
+                let command = Commands::Search {
+                    all: self.all,
+                    attributes,
+                    secret_only: false,
+                    hex: false,
+                    json: false,
+                };
+                if let Output::Items(items, _) = command.run(self.arguments(), None).await? {
+                    for item in items {
+                        print_item(&item);
+                    }
+                }
+            }
+            "lock" => {
+                Commands::Lock.run(self.arguments(), None).await?;
+            }
+            _ => unreachable!(),
+        }
+        Ok(0)
+    }
+}
+
+/// Read the password from the terminal or the whole standard input.
+fn read_password() -> Result<oo7::Secret, Error> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        let password = rpassword::prompt_password("Password: ")
+            .map_err(|_| Error::new("Can't read password"))?;
+        Ok(oo7::Secret::text(password))
+    } else {
+        let mut password = Vec::new();
+        stdin.read_to_end(&mut password)?;
+        Ok(match String::from_utf8(password) {
+            Ok(text) => oo7::Secret::text(text),
+            Err(err) => oo7::Secret::blob(err.into_bytes()),
+        })
+    }
+}
+
+/// Write the password, with a new line only for terminals.
+fn write_password(secret: &oo7::Secret) -> Result<(), Error> {
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(secret.as_bytes())?;
+    if stdout.is_terminal() {
+        stdout.write_all(b"\n")?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+fn print_item(item: &ItemOutput) {
+    println!("[{}]", item.path.as_deref().unwrap_or_default());
+    println!("label = {}", item.label);
+    if let Some(secret) = &item.secret {
+        println!("secret = {secret}");
+    }
+    if let Some(created_at) = &item.created_at {
+        println!("created = {created_at}");
+    }
+    if let Some(modified_at) = &item.modified_at {
+        println!("modified = {modified_at}");
+    }
+    if let Some(schema) = &item.schema {
+        println!("schema = {schema}");
+    }
+    if let Some(attributes) = &item.attributes {
+        let mut attributes = attributes.iter().collect::<Vec<_>>();
+        attributes.sort();
+        for (key, value) in attributes {
+            println!("attribute.{key} = {value}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Invocation, Option<String>> {
+        let mut args = args.iter().map(|arg| (*arg).to_owned());
+        let action = args.next().unwrap();
+        Invocation::parse(&action, args)
+    }
+
+    fn attributes(invocation: &Invocation) -> Vec<(&str, &str)> {
+        invocation
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn store() {
+        let invocation = parse(&["store", "--label=My label", "service", "mail"]).unwrap();
+        assert_eq!(invocation.label.as_deref(), Some("My label"));
+        assert_eq!(invocation.collection, None);
+        assert_eq!(attributes(&invocation), [("service", "mail")]);
+
+        let invocation = parse(&[
+            "store", "-l", "Label", "-c", "login", "user", "alice", "--", "--port", "25",
+        ])
+        .unwrap();
+        assert_eq!(invocation.label.as_deref(), Some("Label"));
+        assert_eq!(invocation.collection.as_deref(), Some("login"));
+        assert_eq!(
+            attributes(&invocation),
+            [("user", "alice"), ("--port", "25")]
+        );
+
+        let invocation = parse(&["store", "service", "mail", "--label", "Label"]).unwrap();
+        assert_eq!(invocation.label.as_deref(), Some("Label"));
+        assert_eq!(attributes(&invocation), [("service", "mail")]);
+    }
+
+    #[test]
+    fn store_invalid() {
+        assert!(matches!(parse(&["store", "service", "mail"]), Err(Some(_))));
+        assert!(matches!(
+            parse(&["store", "service", "mail", "--label"]),
+            Err(Some(_))
+        ));
+        assert!(matches!(parse(&["store", "--label=Label"]), Err(Some(_))));
+        assert!(matches!(
+            parse(&["store", "--label=Label", "service"]),
+            Err(Some(_))
+        ));
+        assert!(matches!(
+            parse(&["store", "--label=Label", "--all", "service", "mail"]),
+            Err(Some(_))
+        ));
+    }
+
+    #[test]
+    fn lookup_clear() {
+        for action in ["lookup", "clear"] {
+            let invocation = parse(&[action, "service", "mail", "user", "alice"]).unwrap();
+            assert_eq!(invocation.action, action);
+            assert_eq!(
+                attributes(&invocation),
+                [("service", "mail"), ("user", "alice")]
+            );
+            assert!(parse(&[action]).is_err());
+            assert!(parse(&[action, "--label=Label", "service", "mail"]).is_err());
+        }
+    }
+
+    #[test]
+    fn search() {
+        let invocation = parse(&["search", "service", "mail"]).unwrap();
+        assert!(!invocation.all && !invocation.unlock);
+
+        let invocation = parse(&["search", "--all", "-u", "service", "mail"]).unwrap();
+        assert!(invocation.all && invocation.unlock);
+        assert_eq!(attributes(&invocation), [("service", "mail")]);
+    }
+
+    #[test]
+    fn lock() {
+        let invocation = parse(&["lock", "--collection=login"]).unwrap();
+        assert_eq!(invocation.collection.as_deref(), Some("login"));
+        assert!(invocation.attributes.is_empty());
+
+        let invocation = parse(&[
+            "lock",
+            "--collection",
+            "/org/freedesktop/secrets/collection/login",
+        ])
+        .unwrap();
+        assert_eq!(
+            invocation.collection.as_deref(),
+            Some("/org/freedesktop/secrets/collection/login")
+        );
+
+        assert!(matches!(parse(&["lock"]), Err(Some(_))));
+        assert!(matches!(
+            parse(&["lock", "--collection=login", "service"]),
+            Err(None)
+        ));
+    }
+
+    #[test]
+    fn unknown_action() {
+        assert!(matches!(parse(&["unlock", "service", "mail"]), Err(None)));
+    }
+}
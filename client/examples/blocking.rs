@@ -0,0 +1,15 @@
+fn main() -> oo7::Result<()> {
+    let keyring = oo7::blocking::Keyring::new()?;
+    let attributes = &[("attr", "value")];
+    keyring.create_item("Some Label", attributes, "secret", true)?;
+
+    let items = keyring.search_items(attributes)?;
+
+    for item in items {
+        println!("{}", item.label()?);
+        println!("{:#?}", item.attributes()?);
+        println!("{:#?}", item.secret()?);
+    }
+
+    Ok(())
+}
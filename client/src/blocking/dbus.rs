@@ -0,0 +1,265 @@
+//! Blocking versions of the [Secret Service](crate::dbus) types.
+
+use std::{collections::HashMap, time::Duration};
+
+use ashpd::WindowIdentifier;
+use zbus::zvariant::ObjectPath;
+
+use super::block_on;
+use crate::{
+    AsAttributes, Secret,
+    dbus::{self, Error},
+};
+
+/// A blocking version of [`dbus::Service`].
+#[derive(Debug)]
+pub struct Service(dbus::Service);
+
+impl Service {
+    /// See [`dbus::Service::new`].
+    pub fn new() -> Result<Self, Error> {
+        block_on(dbus::Service::new()).map(Self)
+    }
+
+    /// See [`dbus::Service::plain`].
+    pub fn plain() -> Result<Self, Error> {
+        block_on(dbus::Service::plain()).map(Self)
+    }
+
+    /// See [`dbus::Service::encrypted`].
+    pub fn encrypted() -> Result<Self, Error> {
+        block_on(dbus::Service::encrypted()).map(Self)
+    }
+
+    /// See [`dbus::Service::default_collection`].
+    pub fn default_collection(&self) -> Result<Collection, Error> {
+        block_on(self.0.default_collection()).map(Collection)
+    }
+
+    /// See [`dbus::Service::session_collection`].
+    pub fn session_collection(&self) -> Result<Collection, Error> {
+        block_on(self.0.session_collection()).map(Collection)
+    }
+
+    /// See [`dbus::Service::with_alias_or_create`].
+    pub fn with_alias_or_create(
+        &self,
+        alias: &str,
+        label: &str,
+        window_id: Option<WindowIdentifier>,
+    ) -> Result<Collection, Error> {
+        block_on(self.0.with_alias_or_create(alias, label, window_id)).map(Collection)
+    }
+
+    /// See [`dbus::Service::with_alias`].
+    pub fn with_alias(&self, alias: &str) -> Result<Option<Collection>, Error> {
+        block_on(self.0.with_alias(alias)).map(|collection| collection.map(Collection))
+    }
+
+    /// See [`dbus::Service::set_alias`].
+    pub fn set_alias(&self, alias: &str, collection: &Collection) -> Result<(), Error> {
+        block_on(self.0.set_alias(alias, &collection.0))
+    }
+
+    /// See [`dbus::Service::collections`].
+    pub fn collections(&self) -> Result<Vec<Collection>, Error> {
+        block_on(self.0.collections())
+            .map(|collections| collections.into_iter().map(Collection).collect())
+    }
+
+    /// See [`dbus::Service::create_collection`].
+    pub fn create_collection(
+        &self,
+        label: &str,
+        alias: Option<&str>,
+        window_id: Option<WindowIdentifier>,
+    ) -> Result<Collection, Error> {
+        block_on(self.0.create_collection(label, alias, window_id)).map(Collection)
+    }
+
+    /// See [`dbus::Service::with_label`].
+    pub fn with_label(&self, label: &str) -> Result<Option<Collection>, Error> {
+        block_on(self.0.with_label(label)).map(|collection| collection.map(Collection))
+    }
+}
+
+impl From<dbus::Service> for Service {
+    fn from(service: dbus::Service) -> Self {
+        Self(service)
+    }
+}
+
+/// A blocking version of [`dbus::Collection`].
+#[derive(Debug)]
+pub struct Collection(dbus::Collection);
+
+impl Collection {
+    /// See [`dbus::Collection::items`].
+    pub fn items(&self) -> Result<Vec<Item>, Error> {
+        block_on(self.0.items()).map(|items| items.into_iter().map(Item).collect())
+    }
+
+    /// See [`dbus::Collection::label`].
+    pub fn label(&self) -> Result<String, Error> {
+        block_on(self.0.label())
+    }
+
+    /// See [`dbus::Collection::set_label`].
+    pub fn set_label(&self, label: &str) -> Result<(), Error> {
+        block_on(self.0.set_label(label))
+    }
+
+    /// See [`dbus::Collection::is_locked`].
+    pub fn is_locked(&self) -> Result<bool, Error> {
+        block_on(self.0.is_locked())
+    }
+
+    /// See [`dbus::Collection::created`].
+    pub fn created(&self) -> Result<Duration, Error> {
+        block_on(self.0.created())
+    }
+
+    /// See [`dbus::Collection::modified`].
+    pub fn modified(&self) -> Result<Duration, Error> {
+        block_on(self.0.modified())
+    }
+
+    /// See [`dbus::Collection::search_items`].
+    pub fn search_items(
+        &self,
+        attributes: &(impl AsAttributes + Sync),
+    ) -> Result<Vec<Item>, Error> {
+        block_on(self.0.search_items(attributes)).map(|items| items.into_iter().map(Item).collect())
+    }
+
+    /// See [`dbus::Collection::create_item`].
+    pub fn create_item(
+        &self,
+        label: &str,
+        attributes: &(impl AsAttributes + Sync),
+        secret: impl Into<Secret>,
+        replace: bool,
+        window_id: Option<WindowIdentifier>,
+    ) -> Result<Item, Error> {
+        let secret = secret.into();
+        block_on(
+            self.0
+                .create_item(label, attributes, secret, replace, window_id),
+        )
+        .map(Item)
+    }
+
+    /// See [`dbus::Collection::unlock`].
+    pub fn unlock(&self, window_id: Option<WindowIdentifier>) -> Result<(), Error> {
+        block_on(self.0.unlock(window_id))
+    }
+
+    /// See [`dbus::Collection::lock`].
+    pub fn lock(&self, window_id: Option<WindowIdentifier>) -> Result<(), Error> {
+        block_on(self.0.lock(window_id))
+    }
+
+    /// See [`dbus::Collection::change_secret`].
+    pub fn change_secret(
+        &self,
+        current: impl Into<Secret>,
+        new: impl Into<Secret>,
+    ) -> Result<(), Error> {
+        let (current, new) = (current.into(), new.into());
+        block_on(self.0.change_secret(current, new))
+    }
+
+    /// See [`dbus::Collection::delete`].
+    pub fn delete(&self, window_id: Option<WindowIdentifier>) -> Result<(), Error> {
+        block_on(self.0.delete(window_id))
+    }
+
+    /// See [`dbus::Collection::path`].
+    pub fn path(&self) -> &ObjectPath<'_> {
+        self.0.path()
+    }
+}
+
+impl From<dbus::Collection> for Collection {
+    fn from(collection: dbus::Collection) -> Self {
+        Self(collection)
+    }
+}
+
+/// A blocking version of [`dbus::Item`].
+#[derive(Debug)]
+pub struct Item(dbus::Item);
+
+impl Item {
+    /// See [`dbus::Item::is_locked`].
+    pub fn is_locked(&self) -> Result<bool, Error> {
+        block_on(self.0.is_locked())
+    }
+
+    /// See [`dbus::Item::label`].
+    pub fn label(&self) -> Result<String, Error> {
+        block_on(self.0.label())
+    }
+
+    /// See [`dbus::Item::set_label`].
+    pub fn set_label(&self, label: &str) -> Result<(), Error> {
+        block_on(self.0.set_label(label))
+    }
+
+    /// See [`dbus::Item::created`].
+    pub fn created(&self) -> Result<Duration, Error> {
+        block_on(self.0.created())
+    }
+
+    /// See [`dbus::Item::modified`].
+    pub fn modified(&self) -> Result<Duration, Error> {
+        block_on(self.0.modified())
+    }
+
+    /// See [`dbus::Item::attributes`].
+    pub fn attributes(&self) -> Result<HashMap<String, String>, Error> {
+        block_on(self.0.attributes())
+    }
+
+    /// See [`dbus::Item::set_attributes`].
+    pub fn set_attributes(&self, attributes: &(impl AsAttributes + Sync)) -> Result<(), Error> {
+        block_on(self.0.set_attributes(attributes))
+    }
+
+    /// See [`dbus::Item::delete`].
+    pub fn delete(&self, window_id: Option<WindowIdentifier>) -> Result<(), Error> {
+        block_on(self.0.delete(window_id))
+    }
+
+    /// See [`dbus::Item::secret`].
+    pub fn secret(&self) -> Result<Secret, Error> {
+        block_on(self.0.secret())
+    }
+
+    /// See [`dbus::Item::set_secret`].
+    pub fn set_secret(&self, secret: impl Into<Secret>) -> Result<(), Error> {
+        let secret = secret.into();
+        block_on(self.0.set_secret(secret))
+    }
+
+    /// See [`dbus::Item::unlock`].
+    pub fn unlock(&self, window_id: Option<WindowIdentifier>) -> Result<(), Error> {
+        block_on(self.0.unlock(window_id))
+    }
+
+    /// See [`dbus::Item::lock`].
+    pub fn lock(&self, window_id: Option<WindowIdentifier>) -> Result<(), Error> {
+        block_on(self.0.lock(window_id))
+    }
+
+    /// See [`dbus::Item::path`].
+    pub fn path(&self) -> &ObjectPath<'_> {
+        self.0.path()
+    }
+}
+
+impl From<dbus::Item> for Item {
+    fn from(item: dbus::Item) -> Self {
+        Self(item)
+    }
+}
@@ -0,0 +1,110 @@
+//! Blocking versions of the [file backend](crate::file) types.
+
+use std::path::Path;
+
+use super::block_on;
+use crate::{
+    AsAttributes, Secret,
+    file::{self, Error, InvalidItemError, UnlockedItem},
+};
+
+/// A blocking version of [`file::UnlockedKeyring`].
+#[derive(Debug)]
+pub struct UnlockedKeyring(file::UnlockedKeyring);
+
+impl UnlockedKeyring {
+    /// See [`file::UnlockedKeyring::load`].
+    pub fn load(path: impl AsRef<Path>, secret: Secret) -> Result<Self, Error> {
+        let path = path.as_ref();
+        block_on(file::UnlockedKeyring::load(path, secret)).map(Self)
+    }
+
+    /// See [`file::UnlockedKeyring::temporary`].
+    pub fn temporary(secret: Secret) -> Result<Self, Error> {
+        block_on(file::UnlockedKeyring::temporary(secret)).map(Self)
+    }
+
+    /// See [`file::UnlockedKeyring::open`].
+    pub fn open(name: &str, secret: Secret) -> Result<Self, Error> {
+        block_on(file::UnlockedKeyring::open(name, secret)).map(Self)
+    }
+
+    /// See [`file::UnlockedKeyring::path`].
+    pub fn path(&self) -> Option<&Path> {
+        self.0.path()
+    }
+
+    /// See [`file::UnlockedKeyring::n_items`].
+    pub fn n_items(&self) -> usize {
+        block_on(self.0.n_items())
+    }
+
+    /// See [`file::UnlockedKeyring::all_items`].
+    pub fn all_items(&self) -> Result<Vec<Result<UnlockedItem, InvalidItemError>>, Error> {
+        block_on(self.0.all_items())
+    }
+
+    /// See [`file::UnlockedKeyring::items`].
+    pub fn items(&self) -> Result<Vec<UnlockedItem>, Error> {
+        block_on(self.0.items())
+    }
+
+    /// See [`file::UnlockedKeyring::search_items`].
+    pub fn search_items(
+        &self,
+        attributes: &(impl AsAttributes + Sync),
+    ) -> Result<Vec<UnlockedItem>, Error> {
+        block_on(self.0.search_items(attributes))
+    }
+
+    /// See [`file::UnlockedKeyring::lookup_item`].
+    pub fn lookup_item(
+        &self,
+        attributes: &(impl AsAttributes + Sync),
+    ) -> Result<Option<UnlockedItem>, Error> {
+        block_on(self.0.lookup_item(attributes))
+    }
+
+    /// See [`file::UnlockedKeyring::delete`].
+    pub fn delete(&self, attributes: &(impl AsAttributes + Sync)) -> Result<(), Error> {
+        block_on(self.0.delete(attributes))
+    }
+
+    /// See [`file::UnlockedKeyring::create_item`].
+    pub fn create_item(
+        &self,
+        label: &str,
+        attributes: &(impl AsAttributes + Sync),
+        secret: impl Into<Secret>,
+        replace: bool,
+    ) -> Result<UnlockedItem, Error> {
+        let secret = secret.into();
+        block_on(self.0.create_item(label, attributes, secret, replace))
+    }
+
+    /// See [`file::UnlockedKeyring::write`].
+    pub fn write(&self) -> Result<(), Error> {
+        block_on(self.0.write())
+    }
+
+    /// See [`file::UnlockedKeyring::change_secret`].
+    pub fn change_secret(&self, secret: Secret) -> Result<(), Error> {
+        block_on(self.0.change_secret(secret))
+    }
+
+    /// See [`file::UnlockedKeyring::validate_secret`].
+    pub fn validate_secret(&self, secret: &Secret) -> Result<bool, Error> {
+        block_on(self.0.validate_secret(secret))
+    }
+
+    /// See [`file::UnlockedKeyring::delete_broken_items`].
+    pub fn delete_broken_items(&self) -> Result<usize, Error> {
+        block_on(self.0.delete_broken_items())
+    }
+}
+
+impl From<file::UnlockedKeyring> for UnlockedKeyring {
+    fn from(keyring: file::UnlockedKeyring) -> Self {
+        Self(keyring)
+    }
+}
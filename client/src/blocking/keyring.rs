@@ -0,0 +1,141 @@
+use std::{collections::HashMap, time::Duration};
+
+use super::block_on;
+use crate::{AsAttributes, KeyringBuilder, Result, Secret};
+
+/// A blocking version of [`crate::Keyring`].
+#[derive(Debug)]
+pub struct Keyring(crate::Keyring);
+
+impl Keyring {
+    /// See [`crate::Keyring::new`].
+    pub fn new() -> Result<Self> {
+        block_on(crate::Keyring::new()).map(Self)
+    }
+
+    /// Create the keyring configured by `builder`.
+    pub fn with_builder(builder: KeyringBuilder) -> Result<Self> {
+        block_on(builder.build()).map(Self)
+    }
+
+    /// See [`crate::Keyring::unlock`].
+    pub fn unlock(&self) -> Result<()> {
+        block_on(self.0.unlock())
+    }
+
+    /// See [`crate::Keyring::lock`].
+    pub fn lock(&self) -> Result<()> {
+        block_on(self.0.lock())
+    }
+
+    /// See [`crate::Keyring::is_locked`].
+    pub fn is_locked(&self) -> Result<bool> {
+        block_on(self.0.is_locked())
+    }
+
+    /// See [`crate::Keyring::delete`].
+    pub fn delete(&self, attributes: &(impl AsAttributes + Sync)) -> Result<()> {
+        block_on(self.0.delete(attributes))
+    }
+
+    /// See [`crate::Keyring::items`].
+    pub fn items(&self) -> Result<Vec<Item>> {
+        block_on(self.0.items()).map(|items| items.into_iter().map(Item).collect())
+    }
+
+    /// See [`crate::Keyring::create_item`].
+    pub fn create_item(
+        &self,
+        label: &str,
+        attributes: &(impl AsAttributes + Sync),
+        secret: impl Into<Secret>,
+        replace: bool,
+    ) -> Result<()> {
+        let secret = secret.into();
+        block_on(self.0.create_item(label, attributes, secret, replace))
+    }
+
+    /// See [`crate::Keyring::search_items`].
+    pub fn search_items(&self, attributes: &(impl AsAttributes + Sync)) -> Result<Vec<Item>> {
+        block_on(self.0.search_items(attributes)).map(|items| items.into_iter().map(Item).collect())
+    }
+}
+
+impl From<crate::Keyring> for Keyring {
+    fn from(keyring: crate::Keyring) -> Self {
+        Self(keyring)
+    }
+}
+
+/// A blocking version of [`crate::Item`].
+#[derive(Debug)]
+pub struct Item(crate::Item);
+
+impl Item {
+    /// See [`crate::Item::label`].
+    pub fn label(&self) -> Result<String> {
+        block_on(self.0.label())
+    }
+
+    /// See [`crate::Item::set_label`].
+    pub fn set_label(&self, label: &str) -> Result<()> {
+        block_on(self.0.set_label(label))
+    }
+
+    /// See [`crate::Item::attributes`].
+    pub fn attributes(&self) -> Result<HashMap<String, String>> {
+        block_on(self.0.attributes())
+    }
+
+    /// See [`crate::Item::set_attributes`].
+    pub fn set_attributes(&self, attributes: &(impl AsAttributes + Sync)) -> Result<()> {
+        block_on(self.0.set_attributes(attributes))
+    }
+
+    /// See [`crate::Item::set_secret`].
+    pub fn set_secret(&self, secret: impl Into<Secret>) -> Result<()> {
+        let secret = secret.into();
+        block_on(self.0.set_secret(secret))
+    }
+
+    /// See [`crate::Item::secret`].
+    pub fn secret(&self) -> Result<Secret> {
+        block_on(self.0.secret())
+    }
+
+    /// See [`crate::Item::is_locked`].
+    pub fn is_locked(&self) -> Result<bool> {
+        block_on(self.0.is_locked())
+    }
+
+    /// See [`crate::Item::lock`].
+    pub fn lock(&self) -> Result<()> {
+        block_on(self.0.lock())
+    }
+
+    /// See [`crate::Item::unlock`].
+    pub fn unlock(&self) -> Result<()> {
+        block_on(self.0.unlock())
+    }
+
+    /// See [`crate::Item::delete`].
+    pub fn delete(&self) -> Result<()> {
+        block_on(self.0.delete())
+    }
+
+    /// See [`crate::Item::created`].
+    pub fn created(&self) -> Result<Duration> {
+        block_on(self.0.created())
+    }
+
+    /// See [`crate::Item::modified`].
+    pub fn modified(&self) -> Result<Duration> {
+        block_on(self.0.modified())
+    }
+}
+
+impl From<crate::Item> for Item {
+    fn from(item: crate::Item) -> Self {
+        Self(item)
+    }
+}
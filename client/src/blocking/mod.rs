@@ -0,0 +1,71 @@
+//! A blocking API, for applications that don't use an async runtime.
+//!
+//! The types mirror the async ones and drive their futures on an internal
+//! runtime. They can be used from inside an existing runtime as well, in which
+//! case the futures are driven from another thread.
+//!
+//! ```no_run
+//! # fn run() -> oo7::Result<()> {
+//! let keyring = oo7::blocking::Keyring::new()?;
+//! let items = keyring.search_items(&[("service", "example")])?;
+//! if let Some(item) = items.first() {
+//!     println!("{:?}", item.secret()?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+
+pub mod dbus;
+pub mod file;
+mod keyring;
+
+pub use keyring::{Item, Keyring};
+
+#[cfg(feature = "tokio")]
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: std::sync::OnceLock<tokio::runtime::Runtime> = std::sync::OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        // The worker keeps driving the connections between the calls
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("oo7-blocking")
+            .enable_all()
+            .build()
+            .expect("Failed to create the oo7 runtime")
+    })
+}
+
+/// Drive `future` to completion, blocking the current thread.
+pub(crate) fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    #[cfg(feature = "tokio")]
+    {
+        if tokio::runtime::Handle::try_current().is_err() {
+            runtime().block_on(future)
+        } else {
+            // Blocking on a runtime thread panics
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| runtime().block_on(future))
+                    .join()
+                    .unwrap_or_else(|err| std::panic::resume_unwind(err))
+            })
+        }
+    }
+    #[cfg(feature = "async-std")]
+    {
+        // There is no way to know whether we are running on an executor thread,
+        // which blocking could deadlock, so always block on another one
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| async_io::block_on(future))
+                .join()
+                .unwrap_or_else(|err| std::panic::resume_unwind(err))
+        })
+    }
+}
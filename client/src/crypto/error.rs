@@ -10,6 +10,9 @@ pub enum Error {
     PadError(cipher::inout::PadError),
     #[cfg(feature = "native_crypto")]
     UnpadError(cipher::block_padding::UnpadError),
+    #[cfg(feature = "native_crypto")]
+    Aead(aes_gcm::Error),
+    Argon2(argon2::Error),
     Getrandom(getrandom::Error),
 }
 
@@ -34,6 +37,19 @@ impl From<cipher::inout::PadError> for Error {
     }
 }
 
+#[cfg(feature = "native_crypto")]
+impl From<aes_gcm::Error> for Error {
+    fn from(value: aes_gcm::Error) -> Self {
+        Self::Aead(value)
+    }
+}
+
+impl From<argon2::Error> for Error {
+    fn from(value: argon2::Error) -> Self {
+        Self::Argon2(value)
+    }
+}
+
 impl From<getrandom::Error> for Error {
     fn from(value: getrandom::Error) -> Self {
         Self::Getrandom(value)
@@ -47,6 +63,9 @@ impl std::error::Error for Error {
             Self::Openssl(e) => Some(e),
             #[cfg(feature = "native_crypto")]
             Self::UnpadError(_) | Self::PadError(_) => None,
+            #[cfg(feature = "native_crypto")]
+            Self::Aead(_) => None,
+            Self::Argon2(_) => None,
             Self::Getrandom(_) => None,
         }
     }
@@ -61,6 +80,9 @@ impl std::fmt::Display for Error {
             Self::UnpadError(e) => f.write_fmt(format_args!("Wrong padding error: {e}")),
             #[cfg(feature = "native_crypto")]
             Self::PadError(e) => f.write_fmt(format_args!("Wrong padding error: {e}")),
+            #[cfg(feature = "native_crypto")]
+            Self::Aead(e) => f.write_fmt(format_args!("Authenticated encryption error: {e}")),
+            Self::Argon2(e) => f.write_fmt(format_args!("Argon2 key derivation error: {e}")),
             Self::Getrandom(e) => f.write_fmt(format_args!("Random number generation error: {e}")),
         }
     }
@@ -60,4 +60,20 @@ mod test {
         assert_eq!(key.as_ref(), &expected_key[..]);
         assert_eq!(iv, &expected_iv[..]);
     }
+
+    #[test]
+    fn test_derive_mac_key() {
+        // RFC 5869, test case 3
+        let expected_key = &[
+            0x8d, 0xa4, 0xe7, 0x75, 0xa5, 0x63, 0xc1, 0x8f, 0x71, 0x5f, 0x80, 0x2a, 0x06, 0x3c,
+            0x5a, 0x31, 0xb8, 0xa1, 0x1f, 0x5c, 0x5e, 0xe1, 0x87, 0x9e, 0xc3, 0x45, 0x4e, 0x5f,
+            0x3c, 0x73, 0x8d, 0x2d,
+        ];
+        let key = Key::new(vec![0x0b; 22]);
+        let mac_key = derive_mac_key(&key, &[]).unwrap();
+        assert_eq!(mac_key.as_ref(), &expected_key[..]);
+
+        let other_key = derive_mac_key(&key, b"info").unwrap();
+        assert_ne!(other_key.as_ref(), mac_key.as_ref());
+    }
 }
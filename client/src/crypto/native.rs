@@ -3,6 +3,8 @@ use std::{
     sync::LazyLock,
 };
 
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, generic_array::typenum::Unsigned};
+use argon2::Argon2;
 use cbc::cipher::{
     BlockDecryptMut, BlockEncryptMut, BlockSizeUser, IvSizeUser, KeyIvInit, KeySizeUser,
     block_padding::{NoPadding, Pkcs7},
@@ -24,6 +26,7 @@ use crate::{Key, file};
 type EncAlg = cbc::Encryptor<aes::Aes128>;
 type DecAlg = cbc::Decryptor<aes::Aes128>;
 type MacAlg = pbkdf2::hmac::Hmac<sha2::Sha256>;
+type AeadAlg = aes_gcm::Aes256Gcm;
 
 pub fn encrypt(
     data: impl AsRef<[u8]>,
@@ -58,6 +61,41 @@ pub fn decrypt(
         .into())
 }
 
+pub(crate) fn encrypt_aead(
+    data: impl AsRef<[u8]>,
+    key: &Key,
+    nonce: impl AsRef<[u8]>,
+) -> Result<Vec<u8>, super::Error> {
+    Ok(<AeadAlg as KeyInit>::new_from_slice(key.encryption_key())
+        .expect("Invalid key length")
+        .encrypt(nonce.as_ref().into(), data.as_ref())?)
+}
+
+pub(crate) fn decrypt_aead(
+    blob: impl AsRef<[u8]>,
+    key: &Key,
+    nonce: impl AsRef<[u8]>,
+) -> Result<Zeroizing<Vec<u8>>, super::Error> {
+    Ok(<AeadAlg as KeyInit>::new_from_slice(key.encryption_key())
+        .expect("Invalid key length")
+        .decrypt(nonce.as_ref().into(), blob.as_ref())?
+        .into())
+}
+
+pub(crate) fn nonce_len() -> usize {
+    <AeadAlg as AeadCore>::NonceSize::USIZE
+}
+
+pub(crate) fn tag_len() -> usize {
+    <AeadAlg as AeadCore>::TagSize::USIZE
+}
+
+pub(crate) fn generate_nonce() -> Result<Vec<u8>, super::Error> {
+    let mut nonce = vec![0u8; nonce_len()];
+    getrandom::fill(&mut nonce)?;
+    Ok(nonce)
+}
+
 pub(crate) fn decrypt_no_padding(
     blob: impl AsRef<[u8]>,
     key: &Key,
@@ -130,7 +168,7 @@ pub(crate) fn mac_len() -> usize {
 }
 
 pub(crate) fn compute_mac(data: impl AsRef<[u8]>, key: &Key) -> Result<crate::Mac, super::Error> {
-    let mut mac = MacAlg::new_from_slice(key.as_ref()).unwrap();
+    let mut mac = <MacAlg as Mac>::new_from_slice(key.mac_key()).unwrap();
     mac.update(data.as_ref());
     Ok(crate::Mac::new(mac.finalize().into_bytes().to_vec()))
 }
@@ -140,11 +178,21 @@ pub(crate) fn verify_mac(
     key: &Key,
     expected_mac: impl AsRef<[u8]>,
 ) -> Result<bool, super::Error> {
-    let mut mac = MacAlg::new_from_slice(key.as_ref()).unwrap();
+    let mut mac = <MacAlg as Mac>::new_from_slice(key.mac_key()).unwrap();
     mac.update(data.as_ref());
     Ok(mac.verify_slice(expected_mac.as_ref()).is_ok())
 }
 
+/// Derive a MAC key bound to `info` from the MAC key of `key`, using
+/// HKDF-SHA256.
+pub(crate) fn derive_mac_key(key: &Key, info: &[u8]) -> Result<Key, super::Error> {
+    let mut okm = vec![0; MacAlg::output_size()];
+    Hkdf::<Sha256>::new(None, key.mac_key())
+        .expand(info, &mut okm)
+        .expect("hkdf expand should never fail");
+    Ok(Key::new(okm))
+}
+
 pub(crate) fn verify_checksum_md5(digest: impl AsRef<[u8]>, content: impl AsRef<[u8]>) -> bool {
     let mut hasher = Md5::new();
     hasher.update(content.as_ref());
@@ -170,6 +218,28 @@ pub(crate) fn derive_key(
     Ok(key)
 }
 
+pub(crate) fn derive_key_argon2id(
+    secret: impl AsRef<[u8]>,
+    key_strength: Result<(), file::WeakKeyError>,
+    salt: impl AsRef<[u8]>,
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+) -> Result<Key, super::Error> {
+    // An encryption key followed by a MAC key
+    let key_len = AeadAlg::key_size() + MacAlg::output_size();
+    let mut key = Key::new_aead_with_strength(vec![0; key_len], key_strength);
+
+    let params = argon2::Params::new(memory_cost, time_cost, parallelism, Some(key_len))?;
+    Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params).hash_password_into(
+        secret.as_ref(),
+        salt.as_ref(),
+        key.as_mut(),
+    )?;
+
+    Ok(key)
+}
+
 pub(crate) fn legacy_derive_key_and_iv(
     secret: impl AsRef<[u8]>,
     key_strength: Result<(), file::WeakKeyError>,
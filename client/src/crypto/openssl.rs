@@ -10,7 +10,9 @@ use openssl::{
     pkey_ctx::PkeyCtx,
     rand::rand_bytes,
     sign::Signer,
-    symm::{Cipher, Crypter, Mode},
+    symm::{
+        Cipher, Crypter, Mode, decrypt_aead as symm_decrypt_aead, encrypt_aead as symm_encrypt_aead,
+    },
 };
 use zeroize::Zeroizing;
 
@@ -18,6 +20,8 @@ use crate::{Key, Mac, file};
 
 const ENC_ALG: Nid = Nid::AES_128_CBC;
 const MAC_ALG: Nid = Nid::SHA256;
+const AEAD_ALG: Nid = Nid::AES_256_GCM;
+const AEAD_TAG_LEN: usize = 16;
 
 pub fn encrypt(
     data: impl AsRef<[u8]>,
@@ -68,6 +72,62 @@ pub fn decrypt(
     decrypt_with_padding(blob, key, iv, true)
 }
 
+pub(crate) fn encrypt_aead(
+    data: impl AsRef<[u8]>,
+    key: &Key,
+    nonce: impl AsRef<[u8]>,
+) -> Result<Vec<u8>, super::Error> {
+    let cipher = Cipher::from_nid(AEAD_ALG).unwrap();
+    let mut tag = vec![0; AEAD_TAG_LEN];
+    let mut blob = symm_encrypt_aead(
+        cipher,
+        key.encryption_key(),
+        Some(nonce.as_ref()),
+        &[],
+        data.as_ref(),
+        &mut tag,
+    )?;
+    blob.append(&mut tag);
+
+    Ok(blob)
+}
+
+pub(crate) fn decrypt_aead(
+    blob: impl AsRef<[u8]>,
+    key: &Key,
+    nonce: impl AsRef<[u8]>,
+) -> Result<Zeroizing<Vec<u8>>, super::Error> {
+    let cipher = Cipher::from_nid(AEAD_ALG).unwrap();
+    // The tag is appended to the encrypted data
+    let (encrypted_data, tag) = blob
+        .as_ref()
+        .split_at(blob.as_ref().len().saturating_sub(AEAD_TAG_LEN));
+    Ok(symm_decrypt_aead(
+        cipher,
+        key.encryption_key(),
+        Some(nonce.as_ref()),
+        &[],
+        encrypted_data,
+        tag,
+    )?
+    .into())
+}
+
+pub(crate) fn nonce_len() -> usize {
+    let cipher = Cipher::from_nid(AEAD_ALG).unwrap();
+    cipher.iv_len().unwrap()
+}
+
+pub(crate) fn tag_len() -> usize {
+    AEAD_TAG_LEN
+}
+
+pub(crate) fn generate_nonce() -> Result<Vec<u8>, super::Error> {
+    let mut buf = vec![0; nonce_len()];
+    rand_bytes(&mut buf)?;
+    Ok(buf)
+}
+
 pub(crate) fn decrypt_no_padding(
     blob: impl AsRef<[u8]>,
     key: &Key,
@@ -144,12 +204,25 @@ pub(crate) fn mac_len() -> usize {
 
 pub(crate) fn compute_mac(data: impl AsRef<[u8]>, key: &Key) -> Result<Mac, super::Error> {
     let md = MessageDigest::from_nid(MAC_ALG).unwrap();
-    let mac_key = PKey::hmac(key.as_ref())?;
+    let mac_key = PKey::hmac(key.mac_key())?;
     let mut signer = Signer::new(md, &mac_key)?;
     signer.update(data.as_ref())?;
     signer.sign_to_vec().map_err(From::from).map(Mac::new)
 }
 
+/// Derive a MAC key bound to `info` from the MAC key of `key`, using
+/// HKDF-SHA256.
+pub(crate) fn derive_mac_key(key: &Key, info: &[u8]) -> Result<Key, super::Error> {
+    let mut okm = vec![0; mac_len()];
+    let mut ctx = PkeyCtx::new_id(Id::HKDF)?;
+    ctx.derive_init()?;
+    ctx.set_hkdf_md(Md::sha256())?;
+    ctx.set_hkdf_key(key.mac_key())?;
+    ctx.add_hkdf_info(info)?;
+    ctx.derive(Some(okm.as_mut()))?;
+    Ok(Key::new(okm))
+}
+
 pub(crate) fn verify_mac(
     data: impl AsRef<[u8]>,
     key: &Key,
@@ -189,6 +262,32 @@ pub(crate) fn derive_key(
     Ok(key)
 }
 
+pub(crate) fn derive_key_argon2id(
+    secret: impl AsRef<[u8]>,
+    key_strength: Result<(), file::WeakKeyError>,
+    salt: impl AsRef<[u8]>,
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+) -> Result<Key, super::Error> {
+    // An encryption key followed by a MAC key
+    let cipher = Cipher::from_nid(AEAD_ALG).unwrap();
+    let md = MessageDigest::from_nid(MAC_ALG).unwrap();
+    let mut key = Key::new_aead_with_strength(vec![0; cipher.key_len() + md.size()], key_strength);
+
+    // OpenSSL only provides Argon2 since 3.2
+    let params = argon2::Params::new(
+        memory_cost,
+        time_cost,
+        parallelism,
+        Some(key.as_ref().len()),
+    )?;
+    argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+        .hash_password_into(secret.as_ref(), salt.as_ref(), key.as_mut())?;
+
+    Ok(key)
+}
+
 pub(crate) fn legacy_derive_key_and_iv(
     secret: impl AsRef<[u8]>,
     key_strength: Result<(), file::WeakKeyError>,
@@ -0,0 +1,87 @@
+use std::fmt;
+
+use ashpd::WindowIdentifier;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+use super::{DBusSecret, DESTINATION, Prompt};
+use crate::dbus::{Error, ServiceError};
+
+/// GNOME Keyring's private interface, also implemented by oo7-daemon.
+///
+/// It allows to manage the password of a collection, which is not covered by
+/// the Secret Service specification.
+#[doc(alias = "org.gnome.keyring.InternalUnsupportedGuiltRiddenInterface")]
+pub struct InternalInterface(zbus::Proxy<'static>);
+
+impl zbus::proxy::Defaults for InternalInterface {
+    const INTERFACE: &'static Option<zbus::names::InterfaceName<'static>> =
+        &Some(zbus::names::InterfaceName::from_static_str_unchecked(
+            "org.gnome.keyring.InternalUnsupportedGuiltRiddenInterface",
+        ));
+    const DESTINATION: &'static Option<zbus::names::BusName<'static>> = &Some(DESTINATION);
+    const PATH: &'static Option<ObjectPath<'static>> =
+        &Some(ObjectPath::from_static_str_unchecked(
+            "/org/gnome/keyring/InternalUnsupportedGuiltRiddenInterface",
+        ));
+}
+
+impl From<zbus::Proxy<'static>> for InternalInterface {
+    fn from(value: zbus::Proxy<'static>) -> Self {
+        Self(value)
+    }
+}
+
+impl InternalInterface {
+    pub async fn new(connection: &zbus::Connection) -> Result<Self, Error> {
+        zbus::proxy::Builder::new(connection)
+            .build()
+            .await
+            .map_err(From::from)
+    }
+
+    pub fn inner(&self) -> &zbus::Proxy<'static> {
+        &self.0
+    }
+
+    #[doc(alias = "ChangeWithMasterPassword")]
+    pub async fn change_with_master_password(
+        &self,
+        collection: &ObjectPath<'_>,
+        original: &DBusSecret,
+        master: &DBusSecret,
+    ) -> Result<(), Error> {
+        self.inner()
+            .call_method("ChangeWithMasterPassword", &(collection, original, master))
+            .await
+            .map_err::<ServiceError, _>(From::from)?;
+        Ok(())
+    }
+
+    #[doc(alias = "ChangeWithPrompt")]
+    pub async fn change_with_prompt(
+        &self,
+        collection: &ObjectPath<'_>,
+        window_id: Option<WindowIdentifier>,
+    ) -> Result<(), Error> {
+        let prompt_path = self
+            .inner()
+            .call_method("ChangeWithPrompt", &(collection))
+            .await
+            .map_err::<ServiceError, _>(From::from)?
+            .body()
+            .deserialize::<OwnedObjectPath>()?;
+
+        if let Some(prompt) = Prompt::new(self.inner().connection(), prompt_path).await? {
+            prompt.receive_completed(window_id).await?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for InternalInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InternalInterface")
+            .field(&self.inner().path().as_str())
+            .finish()
+    }
+}
@@ -14,6 +14,7 @@ impl Unlockable for &zbus::zvariant::ObjectPath<'_> {}
 impl Unlockable for &zbus::zvariant::OwnedObjectPath {}
 
 mod collection;
+mod internal;
 mod item;
 mod prompt;
 mod properties;
@@ -22,6 +23,7 @@ mod service;
 mod session;
 
 pub use collection::Collection;
+pub use internal::InternalInterface;
 pub use item::Item;
 pub use prompt::Prompt;
 #[cfg(not(feature = "unstable"))]
@@ -52,6 +52,21 @@ impl Collection {
         }
     }
 
+    pub(crate) fn inner(&self) -> &api::Collection {
+        &self.inner
+    }
+
+    fn dbus_secret(&self, secret: impl Into<Secret>) -> Result<api::DBusSecret, Error> {
+        match self.algorithm {
+            Algorithm::Plain => Ok(api::DBusSecret::new(Arc::clone(&self.session), secret)),
+            Algorithm::Encrypted => api::DBusSecret::new_encrypted(
+                Arc::clone(&self.session),
+                secret,
+                self.aes_key.as_ref().unwrap(),
+            ),
+        }
+    }
+
     pub(crate) async fn is_available(&self) -> bool {
         *self.available.read().await
     }
@@ -151,14 +166,7 @@ impl Collection {
         if !self.is_available().await {
             Err(Error::Deleted)
         } else {
-            let secret = match self.algorithm {
-                Algorithm::Plain => api::DBusSecret::new(Arc::clone(&self.session), secret),
-                Algorithm::Encrypted => api::DBusSecret::new_encrypted(
-                    Arc::clone(&self.session),
-                    secret,
-                    self.aes_key.as_ref().unwrap(),
-                )?,
-            };
+            let secret = self.dbus_secret(secret)?;
             let item = self
                 .inner
                 .create_item(label, attributes, &secret, replace, window_id)
@@ -192,6 +200,45 @@ impl Collection {
         }
     }
 
+    /// Change the secret used to unlock the collection.
+    ///
+    /// The collection gets unlocked with `current` first. This relies on a
+    /// private interface implemented by GNOME Keyring and oo7-daemon only.
+    pub async fn change_secret(
+        &self,
+        current: impl Into<Secret>,
+        new: impl Into<Secret>,
+    ) -> Result<(), Error> {
+        if !self.is_available().await {
+            Err(Error::Deleted)
+        } else {
+            let current = self.dbus_secret(current)?;
+            let new = self.dbus_secret(new)?;
+            api::InternalInterface::new(self.service.inner().connection())
+                .await?
+                .change_with_master_password(self.path(), &current, &new)
+                .await
+        }
+    }
+
+    /// Change the secret used to unlock the collection, letting the Secret
+    /// Service prompt the user for the new one.
+    ///
+    /// Like [`Collection::change_secret`], it relies on a private interface.
+    pub async fn change_secret_with_prompt(
+        &self,
+        window_id: Option<WindowIdentifier>,
+    ) -> Result<(), Error> {
+        if !self.is_available().await {
+            Err(Error::Deleted)
+        } else {
+            api::InternalInterface::new(self.service.inner().connection())
+                .await?
+                .change_with_prompt(self.path(), window_id)
+                .await
+        }
+    }
+
     /// Delete the collection.
     pub async fn delete(&self, window_id: Option<WindowIdentifier>) -> Result<(), Error> {
         if !self.is_available().await {
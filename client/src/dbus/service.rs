@@ -49,11 +49,23 @@ impl Service {
     /// Create a new instance of the Service, an encrypted communication would
     /// be attempted first and would fall back to a plain one if that fails.
     pub async fn new() -> Result<Self, Error> {
-        let service = match Self::encrypted().await {
+        let cnx = Self::session_connection().await?;
+        Self::new_with_connection(&cnx).await
+    }
+
+    /// Create a new instance of the Service on an existing connection, a
+    /// private bus or a peer-to-peer connection to the service for example.
+    ///
+    /// Like [`Service::new`], an encrypted communication would be attempted
+    /// first.
+    pub async fn new_with_connection(cnx: &zbus::Connection) -> Result<Self, Error> {
+        let service = match Self::with_algorithm(cnx, Algorithm::Encrypted).await {
             Ok(service) => Ok(service),
-            Err(Error::ZBus(zbus::Error::MethodError(..))) => Self::plain().await,
+            Err(Error::ZBus(zbus::Error::MethodError(..))) => {
+                Self::with_algorithm(cnx, Algorithm::Plain).await
+            }
             Err(Error::Service(ServiceError::ZBus(zbus::Error::MethodError(..)))) => {
-                Self::plain().await
+                Self::with_algorithm(cnx, Algorithm::Plain).await
             }
             Err(e) => Err(e),
         }?;
@@ -62,22 +74,26 @@ impl Service {
 
     /// Create a new instance of the Service with plain algorithm.
     pub async fn plain() -> Result<Self, Error> {
-        Self::with_algorithm(Algorithm::Plain).await
+        let cnx = Self::session_connection().await?;
+        Self::with_algorithm(&cnx, Algorithm::Plain).await
     }
 
     /// Create a new instance of the Service with encrypted algorithm.
     pub async fn encrypted() -> Result<Self, Error> {
-        Self::with_algorithm(Algorithm::Encrypted).await
+        let cnx = Self::session_connection().await?;
+        Self::with_algorithm(&cnx, Algorithm::Encrypted).await
     }
 
-    /// Create a new instance of the Service.
-    async fn with_algorithm(algorithm: Algorithm) -> Result<Self, Error> {
-        let cnx = zbus::connection::Builder::session()?
+    async fn session_connection() -> Result<zbus::Connection, Error> {
+        Ok(zbus::connection::Builder::session()?
             .method_timeout(std::time::Duration::from_secs(30))
             .build()
-            .await?;
+            .await?)
+    }
 
-        let service = Arc::new(api::Service::new(&cnx).await?);
+    /// Create a new instance of the Service.
+    async fn with_algorithm(cnx: &zbus::Connection, algorithm: Algorithm) -> Result<Self, Error> {
+        let service = Arc::new(api::Service::new(cnx).await?);
 
         let (aes_key, session) = match algorithm {
             Algorithm::Plain => {
@@ -153,6 +169,14 @@ impl Service {
             .map(|collection| self.new_collection(collection)))
     }
 
+    /// Make an alias point to a collection.
+    ///
+    /// The collection can then be retrieved with [`Service::with_alias`].
+    #[doc(alias = "SetAlias")]
+    pub async fn set_alias(&self, alias: &str, collection: &Collection) -> Result<(), Error> {
+        self.inner.set_alias(alias, collection.inner()).await
+    }
+
     /// Get a list of all the available collections.
     pub async fn collections(&self) -> Result<Vec<Collection>, Error> {
         Ok(self
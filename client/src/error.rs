@@ -10,6 +10,11 @@ pub enum Error {
     File(crate::file::Error),
     /// Secret Service error.
     DBus(crate::dbus::Error),
+    /// Unknown backend name.
+    InvalidBackend(String),
+    /// No backend to try, with an empty
+    /// [`KeyringBuilder::fallback_order`](crate::KeyringBuilder::fallback_order).
+    NoBackend,
 }
 
 impl From<crate::file::Error> for Error {
@@ -31,6 +36,8 @@ impl fmt::Display for Error {
         match self {
             Self::File(e) => write!(f, "File backend error {e}"),
             Self::DBus(e) => write!(f, "DBus error {e}"),
+            Self::InvalidBackend(name) => write!(f, "Unknown backend {name}"),
+            Self::NoBackend => write!(f, "No backend to use"),
         }
     }
 }
@@ -2,10 +2,39 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 use zbus::zvariant::Type;
+use zeroize::Zeroizing;
 
 use super::{Error, UnlockedItem};
 use crate::{Key, Mac, crypto};
 
+/// Size of the smallest bucket the item plaintexts are padded into when hiding
+/// the metadata, the next ones doubling in size.
+const MIN_PADDED_LEN: usize = 256;
+/// Marks the end of the plaintext, followed by zeros up to the bucket size.
+const PADDING_MARKER: u8 = 0x80;
+
+/// Pad `data` to the size of the smallest bucket that fits it, so that only
+/// its order of magnitude is revealed.
+pub(crate) fn pad(data: &[u8]) -> Zeroizing<Vec<u8>> {
+    let len = (data.len() + 1).next_power_of_two().max(MIN_PADDED_LEN);
+    let mut padded = Zeroizing::new(Vec::with_capacity(len));
+    padded.extend_from_slice(data);
+    padded.push(PADDING_MARKER);
+    padded.resize(len, 0);
+    padded
+}
+
+pub(super) fn unpad(mut data: Zeroizing<Vec<u8>>) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let Some(end) = data.iter().rposition(|b| *b != 0) else {
+        return Err(Error::MacError);
+    };
+    if data[end] != PADDING_MARKER {
+        return Err(Error::MacError);
+    }
+    data.truncate(end);
+    Ok(data)
+}
+
 #[derive(Deserialize, Serialize, Type, Debug, Clone)]
 pub(crate) struct EncryptedItem {
     pub(crate) hashed_attributes: HashMap<String, Mac>,
@@ -19,6 +48,37 @@ impl EncryptedItem {
     }
 
     fn try_decrypt_inner(&self, key: &Key) -> Result<UnlockedItem, Error> {
+        let decrypted = if key.is_aead() {
+            self.decrypt_aead(key)?
+        } else {
+            self.decrypt_cbc(key)?
+        };
+
+        let item = UnlockedItem::try_from(decrypted.as_slice())?;
+
+        Self::validate(&self.hashed_attributes, &item, key)?;
+
+        Ok(item)
+    }
+
+    fn decrypt_aead(&self, key: &Key) -> Result<Zeroizing<Vec<u8>>, Error> {
+        // The nonce, the encrypted data and the tag are concatenated into blob.
+        if self.blob.len() < crypto::nonce_len() + crypto::tag_len() {
+            return Err(Error::MacError);
+        }
+        let (nonce, encrypted_data) = self.blob.split_at(crypto::nonce_len());
+
+        // The tag authenticates the item
+        let decrypted =
+            crypto::decrypt_aead(encrypted_data, key, nonce).map_err(|_| Error::MacError)?;
+        if key.hides_metadata() {
+            unpad(decrypted)
+        } else {
+            Ok(decrypted)
+        }
+    }
+
+    fn decrypt_cbc(&self, key: &Key) -> Result<Zeroizing<Vec<u8>>, Error> {
         let n = self.blob.len();
         let n_mac = crypto::mac_len();
         let n_iv = crypto::iv_len();
@@ -34,13 +94,7 @@ impl EncryptedItem {
         let (encrypted_data, iv) = encrypted_data_with_iv.split_at(n - n_mac - n_iv);
 
         // decrypt item
-        let decrypted = crypto::decrypt(encrypted_data, key, iv)?;
-
-        let item = UnlockedItem::try_from(decrypted.as_slice())?;
-
-        Self::validate(&self.hashed_attributes, &item, key)?;
-
-        Ok(item)
+        Ok(crypto::decrypt(encrypted_data, key, iv)?)
     }
 
     pub fn is_valid(&self, key: &Key) -> bool {
@@ -56,8 +110,15 @@ impl EncryptedItem {
         item: &UnlockedItem,
         key: &Key,
     ) -> Result<(), Error> {
+        // The plaintext values by the name they are stored under
+        let attributes = item
+            .attributes()
+            .iter()
+            .map(|(name, value)| Ok((key.attribute_name(name)?, value)))
+            .collect::<Result<HashMap<_, _>, crypto::Error>>()?;
+
         for (attribute_key, hashed_attribute) in hashed_attributes.iter() {
-            if let Some(attribute_plaintext) = item.attributes().get(attribute_key) {
+            if let Some(attribute_plaintext) = attributes.get(attribute_key) {
                 if !crypto::verify_mac(
                     attribute_plaintext.as_bytes(),
                     key,
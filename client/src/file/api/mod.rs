@@ -23,30 +23,52 @@ use serde::{Deserialize, Serialize};
 use tokio::{fs, io, io::AsyncWriteExt};
 use zbus::zvariant::{Endian, Type, serialized::Context};
 
-/// Used for newly created [`Keyring`]s
-const DEFAULT_ITERATION_COUNT: u32 = 100000;
+/// Argon2id memory cost in KiB, used for newly created [`Keyring`]s
+const DEFAULT_MEMORY_COST: u32 = 19 * 1024;
+/// Argon2id number of passes, used for newly created [`Keyring`]s
+const DEFAULT_TIME_COST: u32 = 2;
+/// Argon2id degree of parallelism, used for newly created [`Keyring`]s
+const DEFAULT_PARALLELISM: u32 = 1;
 /// Used for newly created [`Keyring`]s
 const DEFAULT_SALT_SIZE: usize = 32;
 
 const MIN_ITERATION_COUNT: u32 = 100000;
+const MIN_MEMORY_COST: u32 = 19 * 1024;
+const MIN_TIME_COST: u32 = 2;
 const MIN_SALT_SIZE: usize = 32;
+/// Argon2id memory cost in KiB above which a file is considered malformed
+const MAX_MEMORY_COST: u32 = 1024 * 1024;
+const MAX_TIME_COST: u32 = 64;
+const MAX_PARALLELISM: u32 = 64;
 // FIXME: choose a reasonable value
 const MIN_PASSWORD_LENGTH: usize = 4;
 
 const FILE_HEADER: &[u8] = b"GnomeKeyring\n\r\0\n";
 const FILE_HEADER_LEN: usize = FILE_HEADER.len();
+/// Derives the key of the integrity MAC, distinct from the one of the items.
+const INTEGRITY_KEY_INFO: &[u8] = b"oo7 integrity";
 
+/// PBKDF2 and AES-CBC, the format used by libsecret.
+///
+/// The keyrings directory is named after it, the files upgraded to
+/// [`AEAD_MAJOR_VERSION`] are kept there.
 pub(super) const MAJOR_VERSION: u8 = 1;
+/// Argon2id and AES-GCM.
+pub(super) const AEAD_MAJOR_VERSION: u8 = 2;
 const MINOR_VERSION: u8 = 0;
+/// The v2 format, with hashed attribute names and padded items.
+const HIDDEN_METADATA_MINOR_VERSION: u8 = 1;
 
 mod encrypted_item;
 mod legacy_keyring;
 
-pub(super) use encrypted_item::EncryptedItem;
+pub(super) use encrypted_item::{EncryptedItem, pad};
 pub(super) use legacy_keyring::{Keyring as LegacyKeyring, MAJOR_VERSION as LEGACY_MAJOR_VERSION};
 
+#[cfg(any(test, feature = "unstable"))]
+use crate::AsAttributes;
 use crate::{
-    AsAttributes, Key, Secret, crypto,
+    Key, Mac, Secret, crypto,
     file::{Error, UnlockedItem, WeakKeyError},
 };
 
@@ -66,16 +88,112 @@ pub(crate) fn data_dir() -> Option<PathBuf> {
 pub(crate) static GVARIANT_ENCODING: LazyLock<Context> =
     LazyLock::new(|| Context::new_gvariant(Endian::Little, 0));
 
-/// Logical contents of a keyring file
-#[derive(Deserialize, Serialize, Type, Debug)]
-pub struct Keyring {
+/// The key derivation function of a keyring file, which determines its format
+/// version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf {
+    /// PBKDF2-SHA256, used by the v1 format.
+    Pbkdf2 { iteration_count: u32 },
+    /// Argon2id, used by the v2 format.
+    Argon2id {
+        /// Memory cost in KiB.
+        memory_cost: u32,
+        /// Number of passes.
+        time_cost: u32,
+        /// Degree of parallelism.
+        parallelism: u32,
+    },
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Self::Argon2id {
+            memory_cost: DEFAULT_MEMORY_COST,
+            time_cost: DEFAULT_TIME_COST,
+            parallelism: DEFAULT_PARALLELISM,
+        }
+    }
+}
+
+impl Kdf {
+    /// Refuse parameters that would make deriving the key exhaust the memory
+    /// or take forever, as read from a crafted file.
+    fn check_cost(&self) -> Result<(), Error> {
+        match *self {
+            Self::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            } if memory_cost > MAX_MEMORY_COST
+                || time_cost > MAX_TIME_COST
+                || parallelism > MAX_PARALLELISM =>
+            {
+                Err(Error::KdfCostTooHigh(*self))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Kdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pbkdf2 { iteration_count } => {
+                write!(f, "PBKDF2-SHA256, {iteration_count} iterations")
+            }
+            Self::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            } => write!(
+                f,
+                "Argon2id, {memory_cost} KiB, {time_cost} passes, {parallelism} lanes"
+            ),
+        }
+    }
+}
+
+/// Serialized content of a v1 keyring file
+#[derive(Deserialize, Serialize, Type)]
+struct V1Content {
     salt_size: u32,
     #[serde(with = "serde_bytes")]
     salt: Vec<u8>,
     iteration_count: u32,
     modified_time: u64,
     usage_count: u32,
+    items: Vec<EncryptedItem>,
+}
+
+/// Serialized content of a v2 keyring file
+#[derive(Deserialize, Serialize, Type)]
+struct V2Content {
+    salt_size: u32,
+    #[serde(with = "serde_bytes")]
+    salt: Vec<u8>,
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+    modified_time: u64,
+    usage_count: u32,
+    items: Vec<EncryptedItem>,
+    #[serde(with = "serde_bytes")]
+    mac: Vec<u8>,
+}
+
+/// Logical contents of a keyring file
+#[derive(Debug)]
+pub struct Keyring {
+    salt_size: u32,
+    salt: Vec<u8>,
+    kdf: Kdf,
+    modified_time: u64,
+    usage_count: u32,
     pub(in crate::file) items: Vec<EncryptedItem>,
+    /// The integrity MAC of the v2 file it was read from or written to
+    mac: Option<Mac>,
+    /// Whether the attribute names and the item sizes are hidden, v2 only
+    hidden_metadata: bool,
 }
 
 impl Keyring {
@@ -88,7 +206,7 @@ impl Keyring {
         Ok(Self {
             salt_size: salt.len() as u32,
             salt: salt.to_vec(),
-            iteration_count: DEFAULT_ITERATION_COUNT,
+            kdf: Kdf::default(),
             // TODO: UTC?
             modified_time: std::time::SystemTime::UNIX_EPOCH
                 .elapsed()
@@ -96,26 +214,104 @@ impl Keyring {
                 .as_secs(),
             usage_count: 0,
             items: Vec::new(),
+            mac: None,
+            hidden_metadata: false,
         })
     }
 
     pub fn key_strength(&self, secret: &[u8]) -> Result<(), WeakKeyError> {
-        if self.iteration_count < MIN_ITERATION_COUNT {
-            Err(WeakKeyError::IterationCountTooLow(self.iteration_count))
-        } else if self.salt.len() < MIN_SALT_SIZE {
-            Err(WeakKeyError::SaltTooShort(self.salt.len()))
-        } else if secret.len() < MIN_PASSWORD_LENGTH {
+        self.parameters_strength()?;
+        if secret.len() < MIN_PASSWORD_LENGTH {
             Err(WeakKeyError::PasswordTooShort(secret.len()))
         } else {
             Ok(())
         }
     }
 
+    /// Check the strength of the key derivation parameters, the cost of the
+    /// key derivation function and the salt size, which doesn't require the
+    /// secret.
+    pub fn parameters_strength(&self) -> Result<(), WeakKeyError> {
+        match self.kdf {
+            Kdf::Pbkdf2 { iteration_count } if iteration_count < MIN_ITERATION_COUNT => {
+                return Err(WeakKeyError::IterationCountTooLow(iteration_count));
+            }
+            Kdf::Argon2id { memory_cost, .. } if memory_cost < MIN_MEMORY_COST => {
+                return Err(WeakKeyError::MemoryCostTooLow(memory_cost));
+            }
+            Kdf::Argon2id { time_cost, .. } if time_cost < MIN_TIME_COST => {
+                return Err(WeakKeyError::TimeCostTooLow(time_cost));
+            }
+            _ => (),
+        }
+        if self.salt.len() < MIN_SALT_SIZE {
+            Err(WeakKeyError::SaltTooShort(self.salt.len()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The format version of the file, as `(major, minor)`.
+    pub fn version(&self) -> (u8, u8) {
+        match self.kdf {
+            Kdf::Pbkdf2 { .. } => (MAJOR_VERSION, MINOR_VERSION),
+            Kdf::Argon2id { .. } if self.hidden_metadata => {
+                (AEAD_MAJOR_VERSION, HIDDEN_METADATA_MINOR_VERSION)
+            }
+            Kdf::Argon2id { .. } => (AEAD_MAJOR_VERSION, MINOR_VERSION),
+        }
+    }
+
+    /// Whether the attribute names are hashed like their values and the items
+    /// padded, hiding what they are about and the length of their secret.
+    ///
+    /// Only supported by the v2 format.
+    pub fn hidden_metadata(&self) -> bool {
+        self.hidden_metadata && matches!(self.kdf, Kdf::Argon2id { .. })
+    }
+
+    /// Size of the salt used for the key derivation.
+    pub fn salt_size(&self) -> usize {
+        self.salt.len()
+    }
+
+    /// The key derivation function and its parameters.
+    pub fn kdf(&self) -> Kdf {
+        self.kdf
+    }
+
+    /// Number of times the file has been written.
+    pub fn usage_count(&self) -> u32 {
+        self.usage_count
+    }
+
     /// Write to a keyring file
+    ///
+    /// The `key` authenticates the file content, for the v2 format.
     pub async fn dump(
         &mut self,
         path: impl AsRef<Path>,
         mtime: Option<std::time::SystemTime>,
+        key: &Key,
+    ) -> Result<(), Error> {
+        self.dump_inner(path, mtime, Some(key)).await
+    }
+
+    /// Write the file without its integrity MAC, for the items that were not
+    /// encrypted with the key of the keyring.
+    pub(crate) async fn dump_unauthenticated(
+        &mut self,
+        path: impl AsRef<Path>,
+        mtime: Option<std::time::SystemTime>,
+    ) -> Result<(), Error> {
+        self.dump_inner(path, mtime, None).await
+    }
+
+    async fn dump_inner(
+        &mut self,
+        path: impl AsRef<Path>,
+        mtime: Option<std::time::SystemTime>,
+        key: Option<&Key>,
     ) -> Result<(), Error> {
         let tmp_path = if let Some(parent) = path.as_ref().parent() {
             let mut rnd_bytes = [0u8; 8];
@@ -161,7 +357,7 @@ impl Keyring {
             .as_secs();
         self.usage_count += 1;
 
-        let blob = self.as_bytes()?;
+        let blob = self.as_bytes(key)?;
 
         tmpfile.write_all(&blob).await?;
         tmpfile.sync_all().await?;
@@ -175,6 +371,7 @@ impl Keyring {
         };
 
         if mtime != target_mtime {
+            fs::remove_file(&tmp_path).await?;
             return Err(Error::TargetFileChanged(
                 path.as_ref().display().to_string(),
             ));
@@ -185,6 +382,8 @@ impl Keyring {
         Ok(())
     }
 
+    // Going through all the items, UnlockedKeyring uses its index instead
+    #[cfg(any(test, feature = "unstable"))]
     pub fn search_items(
         &self,
         attributes: &impl AsAttributes,
@@ -203,6 +402,7 @@ impl Keyring {
             .collect()
     }
 
+    #[cfg(any(test, feature = "unstable"))]
     pub fn lookup_item(
         &self,
         attributes: &impl AsAttributes,
@@ -221,6 +421,7 @@ impl Keyring {
             .transpose()
     }
 
+    #[cfg(any(test, feature = "unstable"))]
     pub fn lookup_item_index(&self, attributes: &impl AsAttributes, key: &Key) -> Option<usize> {
         let hashed_search = attributes.hash(key);
 
@@ -231,6 +432,7 @@ impl Keyring {
         })
     }
 
+    #[cfg(any(test, feature = "unstable"))]
     pub fn remove_items(&mut self, attributes: &impl AsAttributes, key: &Key) -> Result<(), Error> {
         let hashed_search = attributes.hash(key);
 
@@ -257,12 +459,125 @@ impl Keyring {
         Ok(())
     }
 
-    fn as_bytes(&self) -> Result<Vec<u8>, Error> {
+    /// The data authenticated by the integrity MAC of the v2 format: the
+    /// whole content but the MAC itself, the items in order.
+    fn integrity_data(&self) -> Vec<u8> {
+        fn push_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+            data.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            data.extend_from_slice(bytes);
+        }
+
+        let mut data = FILE_HEADER.to_vec();
+        let (major, minor) = self.version();
+        data.extend_from_slice(&[major, minor]);
+        push_bytes(&mut data, &self.salt);
+        match self.kdf {
+            Kdf::Pbkdf2 { iteration_count } => {
+                data.extend_from_slice(&iteration_count.to_le_bytes());
+            }
+            Kdf::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            } => {
+                data.extend_from_slice(&memory_cost.to_le_bytes());
+                data.extend_from_slice(&time_cost.to_le_bytes());
+                data.extend_from_slice(&parallelism.to_le_bytes());
+            }
+        }
+        data.extend_from_slice(&self.modified_time.to_le_bytes());
+        data.extend_from_slice(&self.usage_count.to_le_bytes());
+        data.extend_from_slice(&(self.items.len() as u64).to_le_bytes());
+        for item in &self.items {
+            let mut hashed_attributes = item.hashed_attributes.iter().collect::<Vec<_>>();
+            hashed_attributes.sort_unstable_by_key(|(name, _)| *name);
+            data.extend_from_slice(&(hashed_attributes.len() as u64).to_le_bytes());
+            for (name, mac) in hashed_attributes {
+                push_bytes(&mut data, name.as_bytes());
+                push_bytes(&mut data, mac.as_slice());
+            }
+            push_bytes(&mut data, &item.blob);
+        }
+        data
+    }
+
+    /// Check the integrity MAC of a v2 file, which authenticates the list of
+    /// items as a whole.
+    ///
+    /// Succeeds for the v1 keyrings, which don't have one. A v2 keyring
+    /// without one, see [`Self::is_authenticated`], can only be loaded
+    /// without validating the secret.
+    pub fn verify_integrity(&self, key: &Key) -> Result<(), Error> {
+        let Some(ref mac) = self.mac else {
+            return match self.version() {
+                (MAJOR_VERSION, _) => Ok(()),
+                _ => Err(Error::IntegrityMismatch),
+            };
+        };
+        let integrity_key = crypto::derive_mac_key(key, INTEGRITY_KEY_INFO)?;
+        if crypto::verify_mac(self.integrity_data(), &integrity_key, mac.as_slice())? {
+            Ok(())
+        } else {
+            Err(Error::IntegrityMismatch)
+        }
+    }
+
+    /// Whether the keyring has an integrity MAC, which is not the case of the
+    /// v1 files, and of the v2 ones written without validating the secret.
+    pub fn is_authenticated(&self) -> bool {
+        self.mac.is_some()
+    }
+
+    fn as_bytes(&mut self, key: Option<&Key>) -> Result<Vec<u8>, Error> {
         let mut blob = FILE_HEADER.to_vec();
 
-        blob.push(MAJOR_VERSION);
-        blob.push(MINOR_VERSION);
-        blob.append(&mut zvariant::to_bytes(*GVARIANT_ENCODING, &self)?.to_vec());
+        let (major, minor) = self.version();
+        blob.push(major);
+        blob.push(minor);
+        let content = match self.kdf {
+            Kdf::Pbkdf2 { iteration_count } => zvariant::to_bytes(
+                *GVARIANT_ENCODING,
+                &V1Content {
+                    salt_size: self.salt_size,
+                    salt: self.salt.clone(),
+                    iteration_count,
+                    modified_time: self.modified_time,
+                    usage_count: self.usage_count,
+                    items: self.items.clone(),
+                },
+            )?,
+            Kdf::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            } => {
+                self.mac = key
+                    .map(|key| {
+                        let integrity_key = crypto::derive_mac_key(key, INTEGRITY_KEY_INFO)?;
+                        crypto::compute_mac(self.integrity_data(), &integrity_key)
+                    })
+                    .transpose()?;
+                zvariant::to_bytes(
+                    *GVARIANT_ENCODING,
+                    &V2Content {
+                        salt_size: self.salt_size,
+                        salt: self.salt.clone(),
+                        memory_cost,
+                        time_cost,
+                        parallelism,
+                        modified_time: self.modified_time,
+                        usage_count: self.usage_count,
+                        items: self.items.clone(),
+                        mac: self
+                            .mac
+                            .as_ref()
+                            .map(|mac| mac.as_slice().to_vec())
+                            .unwrap_or_default(),
+                    },
+                )?
+            }
+        };
+        blob.extend_from_slice(&content);
 
         Ok(blob)
     }
@@ -285,12 +600,27 @@ impl Keyring {
     }
 
     pub fn derive_key(&self, secret: &Secret) -> Result<Key, crypto::Error> {
-        crypto::derive_key(
-            &**secret,
-            self.key_strength(secret),
-            &self.salt,
-            self.iteration_count.try_into().unwrap(),
-        )
+        match self.kdf {
+            Kdf::Pbkdf2 { iteration_count } => crypto::derive_key(
+                &**secret,
+                self.key_strength(secret),
+                &self.salt,
+                iteration_count.try_into().unwrap(),
+            ),
+            Kdf::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            } => crypto::derive_key_argon2id(
+                &**secret,
+                self.key_strength(secret),
+                &self.salt,
+                memory_cost,
+                time_cost,
+                parallelism,
+            )
+            .map(|key| key.with_hidden_metadata(self.hidden_metadata)),
+        }
     }
 
     /// Validate that a secret can decrypt the items in this keyring.
@@ -316,6 +646,40 @@ impl Keyring {
         std::time::Duration::from_secs(self.modified_time)
     }
 
+    /// Whether the same key is derived from a secret for both keyrings.
+    pub(crate) fn has_same_key_parameters(&self, other: &Self) -> bool {
+        self.salt == other.salt && self.kdf == other.kdf
+    }
+
+    /// A keyring sharing the key derivation parameters, and thus the key, of
+    /// this one but holding only `items`.
+    pub(crate) fn with_items(&self, items: Vec<EncryptedItem>) -> Self {
+        Self {
+            salt_size: self.salt_size,
+            salt: self.salt.clone(),
+            kdf: self.kdf,
+            modified_time: self.modified_time,
+            usage_count: 0,
+            items,
+            mac: None,
+            hidden_metadata: self.hidden_metadata,
+        }
+    }
+
+    /// An empty keyring using `kdf` and a new salt for the key derivation,
+    /// hiding the metadata of its items if `hidden_metadata` is set.
+    /// Fails if the cost of `kdf` is too high to read the file back.
+    pub(crate) fn rekeyed(&self, kdf: Kdf, hidden_metadata: bool) -> Result<Self, Error> {
+        kdf.check_cost()?;
+        Ok(Self {
+            kdf,
+            modified_time: self.modified_time,
+            usage_count: self.usage_count,
+            hidden_metadata,
+            ..Self::new()?
+        })
+    }
+
     // Reset Keyring content
     pub(crate) fn reset(&mut self) -> Result<(), Error> {
         let mut salt = [0u8; DEFAULT_SALT_SIZE];
@@ -323,8 +687,11 @@ impl Keyring {
             .map_err(|e| Error::Crypto(crate::crypto::Error::Getrandom(e)))?;
         self.salt_size = salt.len() as u32;
         self.salt = salt.to_vec();
-        self.iteration_count = DEFAULT_ITERATION_COUNT;
-        self.usage_count = 0;
+        // Kept, unless PBKDF2 which is only supported for reading v1 files
+        if let Kdf::Pbkdf2 { .. } = self.kdf {
+            self.kdf = Kdf::default();
+        }
+        // The usage count is not reset, it only grows for a given file
         self.items = Vec::new();
         Ok(())
     }
@@ -342,25 +709,61 @@ impl TryFrom<&[u8]> for Keyring {
         }
 
         let version = value.get(FILE_HEADER_LEN..(FILE_HEADER_LEN + 2));
-        if version != Some(&[MAJOR_VERSION, MINOR_VERSION]) {
-            return Err(Error::VersionMismatch(version.map(|x| x.to_vec())));
-        }
-
-        if let Some(data) = value.get((FILE_HEADER_LEN + 2)..) {
-            let keyring: Self = zvariant::serialized::Data::new(data, *GVARIANT_ENCODING)
-                .deserialize()?
-                .0;
-
-            if keyring.salt.len() != keyring.salt_size as usize {
-                Err(Error::SaltSizeMismatch(
-                    keyring.salt.len(),
-                    keyring.salt_size,
-                ))
-            } else {
-                Ok(keyring)
+        let Some(data) = value.get((FILE_HEADER_LEN + 2)..) else {
+            return Err(Error::NoData);
+        };
+        let data = zvariant::serialized::Data::new(data, *GVARIANT_ENCODING);
+
+        let keyring = match version {
+            Some([MAJOR_VERSION, MINOR_VERSION]) => {
+                let content: V1Content = data.deserialize()?.0;
+                Self {
+                    salt_size: content.salt_size,
+                    salt: content.salt,
+                    kdf: Kdf::Pbkdf2 {
+                        iteration_count: content.iteration_count,
+                    },
+                    modified_time: content.modified_time,
+                    usage_count: content.usage_count,
+                    items: content.items,
+                    mac: None,
+                    hidden_metadata: false,
+                }
+            }
+            Some(
+                [
+                    AEAD_MAJOR_VERSION,
+                    minor @ (MINOR_VERSION | HIDDEN_METADATA_MINOR_VERSION),
+                ],
+            ) => {
+                let content: V2Content = data.deserialize()?.0;
+                let kdf = Kdf::Argon2id {
+                    memory_cost: content.memory_cost,
+                    time_cost: content.time_cost,
+                    parallelism: content.parallelism,
+                };
+                kdf.check_cost()?;
+                Self {
+                    salt_size: content.salt_size,
+                    salt: content.salt,
+                    kdf,
+                    modified_time: content.modified_time,
+                    usage_count: content.usage_count,
+                    items: content.items,
+                    mac: (!content.mac.is_empty()).then(|| Mac::new(content.mac)),
+                    hidden_metadata: *minor == HIDDEN_METADATA_MINOR_VERSION,
+                }
             }
+            _ => return Err(Error::VersionMismatch(version.map(|x| x.to_vec()))),
+        };
+
+        if keyring.salt.len() != keyring.salt_size as usize {
+            Err(Error::SaltSizeMismatch(
+                keyring.salt.len(),
+                keyring.salt_size,
+            ))
         } else {
-            Err(Error::NoData)
+            Ok(keyring)
         }
     }
 }
@@ -369,7 +772,7 @@ impl TryFrom<&[u8]> for Keyring {
 #[cfg(feature = "tokio")]
 mod tests {
     use super::*;
-    use crate::secret::ContentType;
+    use crate::{file::LockedKeyring, secret::ContentType};
 
     const SECRET: [u8; 64] = [
         44, 173, 251, 20, 203, 56, 241, 169, 91, 54, 51, 244, 40, 40, 202, 92, 71, 233, 174, 17,
@@ -409,7 +812,7 @@ mod tests {
             UnlockedItem::new("My Label", &[("my-tag", "my tag value")], "A Password")
                 .encrypt(&key)?,
         );
-        new_keyring.dump("/tmp/test.keyring", None).await?;
+        new_keyring.dump("/tmp/test.keyring", None, &key).await?;
 
         let blob = tokio::fs::read("/tmp/test.keyring").await?;
 
@@ -424,10 +827,202 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn keyfile_v1_dump_load() -> Result<(), Error> {
+        let mut keyring = Keyring::new()?;
+        keyring.kdf = Kdf::Pbkdf2 {
+            iteration_count: MIN_ITERATION_COUNT,
+        };
+        let key = keyring.derive_key(&SECRET.to_vec().into())?;
+        assert!(!key.is_aead());
+
+        keyring.items.push(
+            UnlockedItem::new("My Label", &[("my-tag", "my tag value")], "A Password")
+                .encrypt(&key)?,
+        );
+        let blob = keyring.as_bytes(Some(&key))?;
+        assert_eq!(blob[FILE_HEADER_LEN..FILE_HEADER_LEN + 2], [1, 0]);
+
+        let loaded_keyring = Keyring::try_from(blob.as_slice())?;
+        assert_eq!(loaded_keyring.version(), (1, 0));
+        assert_eq!(loaded_keyring.kdf(), keyring.kdf());
+        let loaded_items = loaded_keyring.search_items(&[("my-tag", "my tag value")], &key)?;
+        assert_eq!(loaded_items[0].secret(), Secret::text("A Password"));
+
+        // The items encrypted with a key derived using PBKDF2 can't be decrypted
+        // with one derived using Argon2id, and the other way around
+        let aead_key = Keyring::new()?.derive_key(&SECRET.to_vec().into())?;
+        assert!(aead_key.is_aead());
+        assert!(!loaded_keyring.items[0].is_valid(&aead_key));
+        let item = UnlockedItem::new("My Label", &[("my-tag", "my tag value")], "A Password")
+            .encrypt(&aead_key)?;
+        assert!(item.is_valid(&aead_key));
+        assert!(!item.is_valid(&key));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn kdf_cost_too_high() -> Result<(), Error> {
+        let mut keyring = Keyring::new()?;
+        let key = keyring.derive_key(&SECRET.to_vec().into())?;
+
+        let kdf = Kdf::Argon2id {
+            memory_cost: u32::MAX,
+            time_cost: DEFAULT_TIME_COST,
+            parallelism: DEFAULT_PARALLELISM,
+        };
+        keyring.kdf = kdf;
+        let blob = keyring.as_bytes(Some(&key))?;
+        assert!(matches!(
+            Keyring::try_from(blob.as_slice()),
+            Err(Error::KdfCostTooHigh(k)) if k == kdf
+        ));
+
+        keyring.kdf = Kdf::Argon2id {
+            memory_cost: MAX_MEMORY_COST,
+            time_cost: MAX_TIME_COST,
+            parallelism: MAX_PARALLELISM,
+        };
+        let blob = keyring.as_bytes(Some(&key))?;
+        assert!(Keyring::try_from(blob.as_slice()).is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn integrity() -> Result<(), Error> {
+        let mut keyring = Keyring::new()?;
+        let key = keyring.derive_key(&SECRET.to_vec().into())?;
+        for label in ["first", "second"] {
+            keyring
+                .items
+                .push(UnlockedItem::new(label, &[("label", label)], "A Password").encrypt(&key)?);
+        }
+        let blob = keyring.as_bytes(Some(&key))?;
+
+        let loaded_keyring = Keyring::try_from(blob.as_slice())?;
+        loaded_keyring.verify_integrity(&key)?;
+        // Not computed with the key of the items' MACs
+        let mac = loaded_keyring.mac.as_ref().unwrap();
+        assert!(!crypto::verify_mac(
+            loaded_keyring.integrity_data(),
+            &key,
+            mac.as_slice()
+        )?);
+        let other_key = Keyring::new()?.derive_key(&SECRET.to_vec().into())?;
+        assert!(matches!(
+            loaded_keyring.verify_integrity(&other_key),
+            Err(Error::IntegrityMismatch)
+        ));
+
+        // Removed item
+        let mut tampered = Keyring::try_from(blob.as_slice())?;
+        tampered.items.pop();
+        assert!(matches!(
+            tampered.verify_integrity(&key),
+            Err(Error::IntegrityMismatch)
+        ));
+
+        // Reordered items
+        let mut tampered = Keyring::try_from(blob.as_slice())?;
+        tampered.items.reverse();
+        assert!(matches!(
+            tampered.verify_integrity(&key),
+            Err(Error::IntegrityMismatch)
+        ));
+
+        // Modified usage count
+        let mut tampered = Keyring::try_from(blob.as_slice())?;
+        tampered.usage_count += 1;
+        assert!(matches!(
+            tampered.verify_integrity(&key),
+            Err(Error::IntegrityMismatch)
+        ));
+
+        // Stripped MAC, only accepted without validating the secret
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("stripped.keyring");
+        let mut stripped = Keyring::try_from(blob.as_slice())?;
+        stripped.dump_unauthenticated(&path, None).await?;
+        let stripped = Keyring::try_from(std::fs::read(&path)?.as_slice())?;
+        assert!(!stripped.is_authenticated());
+        assert!(matches!(
+            stripped.verify_integrity(&key),
+            Err(Error::IntegrityMismatch)
+        ));
+        assert!(matches!(
+            LockedKeyring::load(&path)
+                .await?
+                .unlock(SECRET.to_vec().into())
+                .await,
+            Err(Error::IntegrityMismatch)
+        ));
+        let unlocked = unsafe {
+            LockedKeyring::load(&path)
+                .await?
+                .unlock_unchecked(SECRET.to_vec().into())
+                .await?
+        };
+        assert_eq!(unlocked.n_items().await, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn hidden_metadata() -> Result<(), Error> {
+        let mut keyring = Keyring::new()?;
+        keyring.hidden_metadata = true;
+        let key = keyring.derive_key(&SECRET.to_vec().into())?;
+        assert!(key.hides_metadata());
+
+        for secret in ["short", "a much longer password, still in the same bucket"] {
+            keyring
+                .items
+                .push(UnlockedItem::new("My Label", &[("my-tag", secret)], secret).encrypt(&key)?);
+        }
+        assert!(!keyring.items[0].hashed_attributes.contains_key("my-tag"));
+        assert_eq!(keyring.items[0].blob.len(), keyring.items[1].blob.len());
+
+        let blob = keyring.as_bytes(Some(&key))?;
+        assert_eq!(blob[FILE_HEADER_LEN..FILE_HEADER_LEN + 2], [2, 1]);
+
+        let loaded_keyring = Keyring::try_from(blob.as_slice())?;
+        assert!(loaded_keyring.hidden_metadata());
+        loaded_keyring.verify_integrity(&key)?;
+        let loaded_items = loaded_keyring.search_items(&[("my-tag", "short")], &key)?;
+        assert_eq!(loaded_items.len(), 1);
+        assert_eq!(loaded_items[0].secret(), Secret::text("short"));
+        assert_eq!(loaded_items[0].attributes()["my-tag"], "short");
+
+        // The items can only be decrypted by a key hiding the metadata
+        let mut keyring = Keyring::try_from(blob.as_slice())?;
+        keyring.hidden_metadata = false;
+        let key = keyring.derive_key(&SECRET.to_vec().into())?;
+        assert!(!keyring.items[0].is_valid(&key));
+
+        Ok(())
+    }
+
+    #[test]
+    fn padding() {
+        for len in [0, 1, 254, 255, 256, 1000] {
+            let data = vec![0xffu8; len];
+            let padded = pad(&data);
+            assert!(padded.len() > len);
+            assert!(padded.len().is_power_of_two() && padded.len() >= 256);
+            assert_eq!(*encrypted_item::unpad(padded).unwrap(), data);
+        }
+        assert!(encrypted_item::unpad(zeroize::Zeroizing::new(vec![0; 256])).is_err());
+        assert!(encrypted_item::unpad(zeroize::Zeroizing::new(vec![1; 256])).is_err());
+    }
+
     #[tokio::test]
     async fn key_strength() -> Result<(), Error> {
         let mut keyring = Keyring::new()?;
-        keyring.iteration_count = 50000; // Less than MIN_ITERATION_COUNT (100000)
+        keyring.kdf = Kdf::Pbkdf2 {
+            iteration_count: 50000, // Less than MIN_ITERATION_COUNT (100000)
+        };
         let secret = Secret::from("test-password-that-is-long-enough");
         let result = keyring.key_strength(&secret);
         assert!(matches!(
@@ -435,6 +1030,26 @@ mod tests {
             Err(WeakKeyError::IterationCountTooLow(50000))
         ));
 
+        let mut keyring = Keyring::new()?;
+        keyring.kdf = Kdf::Argon2id {
+            memory_cost: 1024, // Less than MIN_MEMORY_COST (19 MiB)
+            time_cost: 2,
+            parallelism: 1,
+        };
+        let secret = Secret::from("test-password-that-is-long-enough");
+        let result = keyring.key_strength(&secret);
+        assert!(matches!(result, Err(WeakKeyError::MemoryCostTooLow(1024))));
+
+        let mut keyring = Keyring::new()?;
+        keyring.kdf = Kdf::Argon2id {
+            memory_cost: DEFAULT_MEMORY_COST,
+            time_cost: 1, // Less than MIN_TIME_COST (2)
+            parallelism: 1,
+        };
+        let secret = Secret::from("test-password-that-is-long-enough");
+        let result = keyring.key_strength(&secret);
+        assert!(matches!(result, Err(WeakKeyError::TimeCostTooLow(1))));
+
         let keyring = Keyring::new()?;
         let secret = Secret::from("ab");
         let result = keyring.key_strength(&secret);
@@ -445,6 +1060,14 @@ mod tests {
         let secret = Secret::from("test-password-that-is-long-enough");
         let result = keyring.key_strength(&secret);
         assert!(matches!(result, Err(WeakKeyError::SaltTooShort(4))));
+        assert!(matches!(
+            keyring.parameters_strength(),
+            Err(WeakKeyError::SaltTooShort(4))
+        ));
+
+        // The secret length is not part of the parameters
+        let keyring = Keyring::new()?;
+        assert!(keyring.parameters_strength().is_ok());
 
         Ok(())
     }
@@ -0,0 +1,78 @@
+//! A monotonic counter stored outside of a keyring file, to detect the file
+//! being replaced by an older copy of it.
+//!
+//! The counter holds the usage count of the last written file, which is
+//! authenticated by the integrity MAC of the v2 format.
+
+use std::path::Path;
+
+#[cfg(feature = "async-std")]
+use async_fs as fs;
+#[cfg(feature = "async-std")]
+use async_fs::unix::OpenOptionsExt;
+#[cfg(feature = "async-std")]
+use futures_lite::AsyncWriteExt;
+#[cfg(feature = "tokio")]
+use tokio::{fs, io::AsyncWriteExt};
+
+use super::Error;
+
+/// Read the counter at `path`, a missing counter being `0`.
+async fn read(path: &Path) -> Result<u32, Error> {
+    match fs::read_to_string(path).await {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err.into()),
+        Ok(content) => content
+            .trim()
+            .parse()
+            .map_err(|err| Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, err))),
+    }
+}
+
+/// Fail if `usage_count` is lower than the counter at `path`, or if the file
+/// is not `authenticated` while one was already written.
+///
+/// The usage count of a file without an integrity MAC, like a v1 one, can be
+/// edited and cannot be trusted.
+pub(super) async fn check(path: &Path, usage_count: u32, authenticated: bool) -> Result<(), Error> {
+    let counter = read(path).await?;
+    if usage_count < counter {
+        #[cfg(feature = "tracing")]
+        tracing::error!("Keyring file rolled back, the counter at {path:?} is {counter}");
+        Err(Error::RolledBack {
+            usage_count,
+            counter,
+        })
+    } else if !authenticated && counter > 0 {
+        #[cfg(feature = "tracing")]
+        tracing::error!("Keyring file not authenticated, the counter at {path:?} is {counter}");
+        Err(Error::IntegrityMismatch)
+    } else {
+        Ok(())
+    }
+}
+
+/// Store `usage_count` in the counter at `path`, unless it is lower.
+pub(super) async fn update(path: &Path, usage_count: u32) -> Result<(), Error> {
+    if usage_count <= read(path).await? {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let mut tmpfile = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp_path)
+        .await?;
+    tmpfile
+        .write_all(usage_count.to_string().as_bytes())
+        .await?;
+    tmpfile.sync_all().await?;
+    fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
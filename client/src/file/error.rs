@@ -13,6 +13,9 @@ pub enum Error {
     GVariantDeserialization(zvariant::Error),
     /// Mismatch between array length and length explicitly stored in keyring
     SaltSizeMismatch(usize, u32),
+    /// Key derivation parameters stored in the file exceed the supported
+    /// maximum.
+    KdfCostTooHigh(super::Kdf),
     /// Key for some reason too weak to trust it for writing
     WeakKey(WeakKeyError),
     /// Input/Output.
@@ -46,6 +49,12 @@ pub enum Error {
     Crypto(crate::crypto::Error),
     /// Keyring or item is locked
     Locked,
+    /// The keyring file doesn't match its integrity MAC, its items were
+    /// removed, reordered or modified.
+    IntegrityMismatch,
+    /// The keyring file is older than the last one written, according to the
+    /// rollback counter.
+    RolledBack { usage_count: u32, counter: u32 },
     /// Schema error.
     #[cfg(feature = "schema")]
     Schema(crate::SchemaError),
@@ -113,6 +122,9 @@ impl std::fmt::Display for Error {
                 f,
                 "Salt size is not as expected. Array: {arr}, Explicit: {explicit}"
             ),
+            Self::KdfCostTooHigh(kdf) => {
+                write!(f, "Key derivation parameters too high: {kdf}")
+            }
             Self::WeakKey(err) => write!(f, "{err}"),
             Self::Io(e) => write!(f, "IO error {e}"),
             Self::MacError => write!(f, "Mac digest is not equal to the expected value"),
@@ -136,6 +148,19 @@ impl std::fmt::Display for Error {
             ),
             Self::Crypto(e) => write!(f, "Failed to do a cryptography operation, {e}"),
             Self::Locked => write!(f, "Keyring or item is locked"),
+            Self::IntegrityMismatch => {
+                write!(
+                    f,
+                    "Keyring file integrity MAC is not equal to the expected value"
+                )
+            }
+            Self::RolledBack {
+                usage_count,
+                counter,
+            } => write!(
+                f,
+                "Keyring file rolled back: written {usage_count} times, expected at least {counter}",
+            ),
             #[cfg(feature = "schema")]
             Self::Schema(e) => write!(f, "Schema error: {e}"),
         }
@@ -157,6 +182,17 @@ impl InvalidItemError {
             attribute_names,
         }
     }
+
+    /// Why the item couldn't be decrypted.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// The names of the item attributes, which are stored in the clear unless
+    /// the metadata of the keyring items is hidden.
+    pub fn attribute_names(&self) -> &[String] {
+        &self.attribute_names
+    }
 }
 
 impl std::error::Error for InvalidItemError {}
@@ -177,9 +213,15 @@ pub enum WeakKeyError {
     /// Avoid attack on existing files
     IterationCountTooLow(u32),
     /// Avoid attack on existing files
+    MemoryCostTooLow(u32),
+    /// Avoid attack on existing files
+    TimeCostTooLow(u32),
+    /// Avoid attack on existing files
     SaltTooShort(usize),
     /// Just not secure enough to store password
     PasswordTooShort(usize),
+    /// PBKDF2 is only supported for reading v1 files
+    KdfObsolete,
     /// Should not occur
     ///
     /// Used by [`dbus`](crate::dbus) module that does not currently
@@ -193,10 +235,13 @@ impl std::fmt::Display for WeakKeyError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::IterationCountTooLow(count) => write!(f, "Iteration count too low: {count}"),
+            Self::MemoryCostTooLow(cost) => write!(f, "Memory cost too low: {cost} KiB"),
+            Self::TimeCostTooLow(cost) => write!(f, "Time cost too low: {cost} passes"),
             Self::SaltTooShort(length) => write!(f, "Salt too short: {length}"),
             Self::PasswordTooShort(length) => {
                 write!(f, "Password (secret from portal) too short: {length}")
             }
+            Self::KdfObsolete => write!(f, "Obsolete key derivation function"),
             Self::StrengthUnknown => write!(f, "Strength unknown"),
         }
     }
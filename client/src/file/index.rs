@@ -0,0 +1,186 @@
+//! Find the items of a keyring by their hashed attributes, without going
+//! through all of them.
+
+use std::collections::HashMap;
+
+use super::{Error, api::EncryptedItem};
+use crate::Mac;
+
+/// The positions of the items in the list of items of a keyring, by the name
+/// and the value MAC of their attributes.
+///
+/// The index is built from the hashed attributes as stored in the file, it
+/// doesn't depend on the key.
+#[derive(Debug, Default)]
+pub(super) struct Index {
+    /// The positions are kept sorted.
+    positions: HashMap<(String, Vec<u8>), Vec<usize>>,
+    n_items: usize,
+}
+
+impl Index {
+    pub(super) fn new(items: &[EncryptedItem]) -> Self {
+        let mut index = Self::default();
+        for (position, item) in items.iter().enumerate() {
+            index.add(position, item);
+        }
+        index.n_items = items.len();
+        index
+    }
+
+    fn entries(item: &EncryptedItem) -> impl Iterator<Item = (String, Vec<u8>)> + '_ {
+        item.hashed_attributes
+            .iter()
+            .map(|(name, mac)| (name.clone(), mac.as_slice().to_vec()))
+    }
+
+    fn add(&mut self, position: usize, item: &EncryptedItem) {
+        for entry in Self::entries(item) {
+            let positions = self.positions.entry(entry).or_default();
+            if let Err(i) = positions.binary_search(&position) {
+                positions.insert(i, position);
+            }
+        }
+    }
+
+    /// Add `item` at the end of `items`, the indexed list.
+    pub(super) fn push(&mut self, items: &mut Vec<EncryptedItem>, item: EncryptedItem) {
+        self.add(items.len(), &item);
+        items.push(item);
+        self.n_items = items.len();
+    }
+
+    /// Replace the item at `position` in `items`, the indexed list, with
+    /// `item`.
+    pub(super) fn replace(
+        &mut self,
+        items: &mut [EncryptedItem],
+        position: usize,
+        item: EncryptedItem,
+    ) -> Result<(), Error> {
+        let Some(old_item) = items.get_mut(position) else {
+            return Err(Error::InvalidItemIndex(position));
+        };
+        for entry in Self::entries(old_item) {
+            if let Some(positions) = self.positions.get_mut(&entry) {
+                positions.retain(|p| *p != position);
+                if positions.is_empty() {
+                    self.positions.remove(&entry);
+                }
+            }
+        }
+        self.add(position, &item);
+        *old_item = item;
+        Ok(())
+    }
+
+    /// Remove the items at the sorted `removed` positions from `items`, the
+    /// indexed list.
+    pub(super) fn remove(&mut self, items: &mut Vec<EncryptedItem>, removed: &[usize]) {
+        if removed.is_empty() {
+            return;
+        }
+        let mut position = 0;
+        items.retain(|_| {
+            let is_removed = removed.binary_search(&position).is_ok();
+            position += 1;
+            !is_removed
+        });
+        self.positions.retain(|_, positions| {
+            positions.retain_mut(|p| match removed.binary_search(p) {
+                Ok(_) => false,
+                Err(n_before) => {
+                    *p -= n_before;
+                    true
+                }
+            });
+            !positions.is_empty()
+        });
+        self.n_items = items.len();
+    }
+
+    /// The sorted positions of the items having all the `hashed_attributes`,
+    /// as returned by [`AsAttributes::hash`](crate::AsAttributes::hash).
+    pub(super) fn search(
+        &self,
+        hashed_attributes: &[(String, Result<Mac, crate::crypto::Error>)],
+    ) -> Vec<usize> {
+        let mut candidates: Option<Vec<usize>> = None;
+        for (name, mac) in hashed_attributes {
+            let Some(positions) = mac
+                .as_ref()
+                .ok()
+                .and_then(|mac| self.positions.get(&(name.clone(), mac.as_slice().to_vec())))
+            else {
+                return Vec::new();
+            };
+            candidates = Some(match candidates {
+                None => positions.clone(),
+                Some(mut candidates) => {
+                    candidates.retain(|p| positions.binary_search(p).is_ok());
+                    candidates
+                }
+            });
+            if candidates.as_ref().is_some_and(Vec::is_empty) {
+                break;
+            }
+        }
+        // Every item matches an empty search
+        candidates.unwrap_or_else(|| (0..self.n_items).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsAttributes, Key, Secret, file::UnlockedItem};
+
+    fn item(attributes: &[(&str, &str)], key: &Key) -> EncryptedItem {
+        UnlockedItem::new("Label", &attributes, Secret::text("secret"))
+            .encrypt(key)
+            .unwrap()
+    }
+
+    #[test]
+    fn search() {
+        let key = Key::new_aead_with_strength(vec![1; 64], Ok(()));
+        let mut items = vec![
+            item(&[("service", "mail"), ("user", "alice")], &key),
+            item(&[("service", "mail"), ("user", "bob")], &key),
+            item(&[("service", "chat"), ("user", "alice")], &key),
+        ];
+        let mut index = Index::new(&items);
+        let search =
+            |index: &Index, attributes: &[(&str, &str)]| index.search(&attributes.hash(&key));
+
+        assert_eq!(search(&index, &[("service", "mail")]), [0, 1]);
+        assert_eq!(search(&index, &[("user", "alice")]), [0, 2]);
+        assert_eq!(
+            search(&index, &[("service", "mail"), ("user", "alice")]),
+            [0]
+        );
+        assert!(search(&index, &[("service", "mail"), ("user", "carol")]).is_empty());
+        assert!(search(&index, &[("service", "alice")]).is_empty());
+        assert_eq!(search(&index, &[]), [0, 1, 2]);
+
+        let new_item = item(&[("service", "mail"), ("user", "carol")], &key);
+        index.replace(&mut items, 1, new_item).unwrap();
+        assert_eq!(search(&index, &[("service", "mail")]), [0, 1]);
+        assert!(search(&index, &[("user", "bob")]).is_empty());
+        assert_eq!(search(&index, &[("user", "carol")]), [1]);
+        assert!(matches!(
+            index.replace(&mut items, 3, item(&[], &key)),
+            Err(Error::InvalidItemIndex(3))
+        ));
+
+        index.remove(&mut items, &[0]);
+        assert_eq!(items.len(), 2);
+        assert_eq!(search(&index, &[("service", "mail")]), [0]);
+        assert_eq!(search(&index, &[("user", "alice")]), [1]);
+        assert_eq!(search(&index, &[]), [0, 1]);
+
+        index.push(&mut items, item(&[("service", "mail")], &key));
+        assert_eq!(search(&index, &[("service", "mail")]), [0, 2]);
+        assert_eq!(search(&index, &[]), [0, 1, 2]);
+    }
+}
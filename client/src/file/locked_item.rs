@@ -10,6 +10,15 @@ pub struct LockedItem {
 }
 
 impl LockedItem {
+    /// The names of the attributes of the item.
+    ///
+    /// Only the attribute values are hashed in the keyring file, their names
+    /// are stored in the clear unless the keyring hides the metadata of its
+    /// items, in which case the hashed names are returned.
+    pub fn attribute_names(&self) -> impl Iterator<Item = &str> {
+        self.inner.hashed_attributes.keys().map(String::as_str)
+    }
+
     /// Unlocks the item.
     pub fn unlock(self, key: &Key) -> Result<UnlockedItem, file::Error> {
         self.inner.decrypt(key)
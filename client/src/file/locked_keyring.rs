@@ -2,7 +2,10 @@
 use std::io;
 use std::{
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
 #[cfg(feature = "async-std")]
@@ -18,7 +21,9 @@ use tokio::{
     sync::{Mutex, RwLock},
 };
 
-use super::{Error, LockedItem, UnlockedKeyring, api};
+use super::{
+    Error, Kdf, LockedItem, UnlockedKeyring, WeakKeyError, api, counter, index::Index, watch,
+};
 use crate::Secret;
 
 /// A locked keyring that requires a secret to unlock.
@@ -27,6 +32,12 @@ pub struct LockedKeyring {
     pub(super) keyring: Arc<RwLock<api::Keyring>>,
     pub(super) path: Option<PathBuf>,
     pub(super) mtime: Mutex<Option<std::time::SystemTime>>,
+    pub(super) merge_on_conflict: bool,
+    /// The rollback counter, stored outside the file
+    pub(super) counter: Option<PathBuf>,
+    /// Whether the content was read from the file, and has to be
+    /// authenticated on unlock
+    pub(super) check_integrity: AtomicBool,
 }
 
 impl LockedKeyring {
@@ -54,6 +65,53 @@ impl LockedKeyring {
         self.keyring.read().await.modified_time()
     }
 
+    /// The format version of the file, as `(major, minor)`.
+    pub async fn version(&self) -> (u8, u8) {
+        self.keyring.read().await.version()
+    }
+
+    /// Size of the salt used for the key derivation.
+    pub async fn salt_size(&self) -> usize {
+        self.keyring.read().await.salt_size()
+    }
+
+    /// The key derivation function and its parameters.
+    pub async fn kdf(&self) -> Kdf {
+        self.keyring.read().await.kdf()
+    }
+
+    /// Number of times the file has been written.
+    pub async fn usage_count(&self) -> u32 {
+        self.keyring.read().await.usage_count()
+    }
+
+    /// Check the strength of the key derivation parameters.
+    ///
+    /// Unlike the check done when unlocking, the length of the secret is not
+    /// taken into account.
+    pub async fn key_strength(&self) -> Result<(), WeakKeyError> {
+        self.keyring.read().await.parameters_strength()
+    }
+
+    /// Reload the keyring if its file was written since it was read, by
+    /// another process for example.
+    ///
+    /// Returns whether the keyring was reloaded.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
+    pub async fn reload(&self) -> Result<bool, Error> {
+        let Some(ref path) = self.path else {
+            return Ok(false);
+        };
+        let mut mtime = self.mtime.lock().await;
+        let Some((keyring, modified)) = watch::read(path, *mtime).await? else {
+            return Ok(false);
+        };
+        *self.keyring.write().await = keyring;
+        *mtime = modified;
+        self.check_integrity.store(true, Ordering::Relaxed);
+        Ok(true)
+    }
+
     /// Retrieve the list of available [`LockedItem`]s without decrypting them.
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
     pub async fn items(&self) -> Result<Vec<LockedItem>, Error> {
@@ -68,7 +126,25 @@ impl LockedKeyring {
             .collect())
     }
 
+    /// Check the number of times the file was written against a counter at
+    /// `path`, outside of the keyring file, when unlocking.
+    ///
+    /// Unlocking fails with [`Error::RolledBack`] if the file was replaced by
+    /// an older copy of it. The counter is updated on every write of the
+    /// unlocked keyring, a missing counter is considered to be `0`. Disabled
+    /// by default.
+    pub fn with_rollback_counter(mut self, path: impl AsRef<Path>) -> Self {
+        self.counter = Some(path.as_ref().to_path_buf());
+        self
+    }
+
     /// Unlocks a keyring and validates it
+    ///
+    /// The content of v2 files is authenticated as a whole, unlocking fails
+    /// with [`Error::IntegrityMismatch`] if items were removed, reordered or
+    /// modified, or if the file has no integrity MAC. Such a file, written
+    /// with items encrypted with different secrets, can only be unlocked with
+    /// [`Self::unlock_unchecked`].
     pub async fn unlock(self, secret: Secret) -> Result<UnlockedKeyring, Error> {
         self.unlock_inner(secret, true).await
     }
@@ -78,7 +154,8 @@ impl LockedKeyring {
     /// # Safety
     ///
     /// The method doesn't validate that the secret can decrypt all the items in
-    /// the keyring.
+    /// the keyring. The file is written without its integrity MAC if items
+    /// encrypted with different secrets end up in it.
     pub async unsafe fn unlock_unchecked(self, secret: Secret) -> Result<UnlockedKeyring, Error> {
         self.unlock_inner(secret, false).await
     }
@@ -103,6 +180,14 @@ impl LockedKeyring {
                 }
             }
 
+            let integrity = if self.check_integrity.load(Ordering::Relaxed) {
+                inner_keyring.verify_integrity(&key)
+            } else {
+                Ok(())
+            };
+            let usage_count = inner_keyring.usage_count();
+            let is_authenticated = inner_keyring.is_authenticated();
+
             drop(inner_keyring);
 
             if n_valid_items == 0 && n_broken_items != 0 {
@@ -126,28 +211,54 @@ impl LockedKeyring {
                 });
             }
 
+            match integrity {
+                // Without any item, the secret cannot be validated otherwise
+                Err(Error::IntegrityMismatch) if n_valid_items == 0 => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("Keyring integrity cannot be verified. Invalid secret.");
+                    return Err(Error::IncorrectSecret);
+                }
+                Err(err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("Keyring file integrity check failed: {err}");
+                    return Err(err);
+                }
+                Ok(()) => (),
+            }
+
+            if let Some(ref counter) = self.counter {
+                counter::check(counter, usage_count, is_authenticated).await?;
+            }
+
             Some(Arc::new(key))
         } else {
             None
         };
 
+        let synced_items = self.keyring.read().await.items.clone();
+        let index = Index::new(&synced_items);
         Ok(UnlockedKeyring {
             keyring: self.keyring,
             path: self.path,
             mtime: self.mtime,
             key: Mutex::new(key),
             secret: Mutex::new(Arc::new(secret)),
+            synced_items: Mutex::new(synced_items),
+            index: RwLock::new(index),
+            merge_on_conflict: self.merge_on_conflict,
+            counter: self.counter,
+            unchecked: !validate_items,
         })
     }
 
     /// Load a keyring from a file path.
     pub async fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
         let path = path.as_ref();
-        let (mtime, keyring) = match fs::File::open(&path).await {
+        let (mtime, keyring, is_read) = match fs::File::open(&path).await {
             Err(err) if err.kind() == io::ErrorKind::NotFound => {
                 #[cfg(feature = "tracing")]
                 tracing::debug!("Keyring file not found, creating a new one");
-                (None, api::Keyring::new()?)
+                (None, api::Keyring::new()?, false)
             }
             Err(err) => return Err(err.into()),
             Ok(mut file) => {
@@ -161,7 +272,7 @@ impl LockedKeyring {
 
                 let keyring = api::Keyring::try_from(content.as_slice())?;
 
-                (mtime, keyring)
+                (mtime, keyring, true)
             }
         };
 
@@ -169,6 +280,9 @@ impl LockedKeyring {
             keyring: Arc::new(RwLock::new(keyring)),
             path: Some(path.to_path_buf()),
             mtime: Mutex::new(mtime),
+            merge_on_conflict: false,
+            counter: None,
+            check_integrity: AtomicBool::new(is_read),
         })
     }
 
@@ -0,0 +1,145 @@
+//! Merge the items of a keyring file written concurrently by another process.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use super::{Error, UnlockedItem, api::EncryptedItem};
+use crate::Key;
+
+/// Identifies the items, the hashed attributes sorted by name.
+type ItemId = Vec<(String, Vec<u8>)>;
+
+fn item_id(item: &EncryptedItem) -> ItemId {
+    let mut id = item
+        .hashed_attributes
+        .iter()
+        .map(|(name, mac)| (name.clone(), mac.as_slice().to_vec()))
+        .collect::<Vec<_>>();
+    id.sort_unstable();
+    id
+}
+
+/// The items sharing the same hashed attributes.
+fn group(items: &[EncryptedItem]) -> BTreeMap<ItemId, Vec<&EncryptedItem>> {
+    let mut groups = BTreeMap::<_, Vec<_>>::new();
+    for item in items {
+        groups.entry(item_id(item)).or_default().push(item);
+    }
+    groups
+}
+
+/// What is compared of an item, its content or its ciphertext if it cannot be
+/// decrypted.
+///
+/// The ciphertext of an item changes every time it is encrypted, with a new
+/// key after an upgrade of the file for example.
+#[derive(PartialEq)]
+enum Content {
+    Decrypted(UnlockedItem),
+    Encrypted(Vec<u8>),
+}
+
+impl Content {
+    fn new(item: &EncryptedItem, key: &Key) -> Self {
+        match item.clone().decrypt(key) {
+            Ok(item) => Self::Decrypted(item),
+            Err(_) => Self::Encrypted(item.blob.clone()),
+        }
+    }
+
+    fn modified(&self) -> Duration {
+        match self {
+            Self::Decrypted(item) => item.modified(),
+            Self::Encrypted(_) => Duration::default(),
+        }
+    }
+}
+
+fn contents(items: Option<&Vec<&EncryptedItem>>, key: &Key) -> Vec<Content> {
+    items
+        .into_iter()
+        .flatten()
+        .map(|item| Content::new(item, key))
+        .collect()
+}
+
+fn is_same(a: &[Content], b: &[Content]) -> bool {
+    let mut b = b.iter().collect::<Vec<_>>();
+    a.len() == b.len()
+        && a.iter().all(|content| {
+            b.iter()
+                .position(|other| *other == content)
+                .map(|pos| b.swap_remove(pos))
+                .is_some()
+        })
+}
+
+/// The most recent modification time of the items.
+fn last_modified(items: &[Content]) -> Duration {
+    items
+        .iter()
+        .map(Content::modified)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Merge the `local` and `remote` items, both derived from `base`.
+///
+/// The items are identified by their hashed attributes. An item changed on a
+/// single side is taken from that side, including its deletion: an item
+/// missing from one side but present in `base` acts as a tombstone. When both
+/// sides changed an item, the last modified one wins and a modification wins
+/// over a deletion.
+///
+/// All the items must be encrypted with `key`, they are compared by content
+/// as their ciphertext depends on the nonce they were encrypted with.
+pub(super) fn merge(
+    base: &[EncryptedItem],
+    local: Vec<EncryptedItem>,
+    remote: Vec<EncryptedItem>,
+    key: &Key,
+) -> Vec<EncryptedItem> {
+    let base_groups = group(base);
+    let local_groups = group(&local);
+    let remote_groups = group(&remote);
+
+    let mut take_remote = BTreeMap::new();
+    for id in local_groups.keys().chain(remote_groups.keys()) {
+        if take_remote.contains_key(id) {
+            continue;
+        }
+        let base = contents(base_groups.get(id), key);
+        let local = contents(local_groups.get(id), key);
+        let remote = contents(remote_groups.get(id), key);
+
+        let is_remote = if is_same(&local, &remote) || is_same(&remote, &base) {
+            false
+        } else if is_same(&local, &base) {
+            true
+        } else if local.is_empty() || remote.is_empty() {
+            local.is_empty()
+        } else {
+            last_modified(&remote) > last_modified(&local)
+        };
+        take_remote.insert(id.clone(), is_remote);
+    }
+
+    let local = local
+        .into_iter()
+        .filter(|item| !take_remote[&item_id(item)]);
+    let remote = remote
+        .into_iter()
+        .filter(|item| take_remote[&item_id(item)]);
+    local.chain(remote).collect()
+}
+
+/// Encrypt the `items` encrypted with `from` with `to` instead.
+pub(super) fn reencrypt(
+    items: Vec<EncryptedItem>,
+    from: &Key,
+    to: &Key,
+) -> Result<Vec<EncryptedItem>, Error> {
+    items
+        .into_iter()
+        .map(|item| item.decrypt(from)?.encrypt(to))
+        .collect()
+}
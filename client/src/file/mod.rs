@@ -1,5 +1,14 @@
 //! File backend implementation that can be backed by the [Secret portal](https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Secret.html).
 //!
+//! New keyrings are written in the v2 format, which derives the key using
+//! Argon2id and encrypts the items using AES-256-GCM. Existing v1 keyrings,
+//! the format used by libsecret, are upgraded on their next write; libsecret
+//! can't read the upgraded files.
+//!
+//! The names of the item attributes are stored in the clear and the size of
+//! the items follows the one of their secret, unless the metadata is hidden
+//! using [`UnlockedKeyring::set_hidden_metadata`].
+//!
 //! ```no_run
 //! use oo7::{Secret, file::UnlockedKeyring};
 //!
@@ -23,12 +32,17 @@ pub mod api;
 #[cfg(not(feature = "unstable"))]
 pub(crate) mod api;
 
+mod counter;
 mod error;
+mod index;
 mod locked_item;
 mod locked_keyring;
+mod merge;
 mod unlocked_item;
 mod unlocked_keyring;
+pub(crate) mod watch;
 
+pub use api::Kdf;
 pub use error::{Error, InvalidItemError, WeakKeyError};
 pub use locked_item::LockedItem;
 pub use locked_keyring::LockedKeyring;
@@ -106,6 +120,7 @@ impl Item {
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Keyring {
     Locked(LockedKeyring),
     Unlocked(UnlockedKeyring),
@@ -157,6 +172,14 @@ impl Keyring {
             .and_then(|time| time.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
     }
 
+    /// Reload the keyring if its file was written since it was read.
+    pub async fn reload(&self) -> Result<bool, Error> {
+        match self {
+            Self::Locked(keyring) => keyring.reload().await,
+            Self::Unlocked(keyring) => keyring.reload().await,
+        }
+    }
+
     pub async fn items(&self) -> Result<Vec<Item>, Error> {
         match self {
             Self::Locked(keyring) => Ok(keyring
@@ -5,7 +5,7 @@ use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 use super::{
     Error, LockedItem,
-    api::{EncryptedItem, GVARIANT_ENCODING},
+    api::{EncryptedItem, GVARIANT_ENCODING, pad},
 };
 use crate::{AsAttributes, CONTENT_TYPE_ATTRIBUTE, Key, Secret, crypto, secret::ContentType};
 
@@ -27,7 +27,11 @@ pub struct UnlockedItem {
 }
 
 impl UnlockedItem {
-    pub(crate) fn new(
+    /// Create a new item, created and modified now.
+    ///
+    /// The item is only stored once added to a keyring with
+    /// [`UnlockedKeyring::insert_items`](super::UnlockedKeyring::insert_items).
+    pub fn new(
         label: impl ToString,
         attributes: &impl AsAttributes,
         secret: impl Into<Secret>,
@@ -159,6 +163,15 @@ impl UnlockedItem {
         Duration::from_secs(self.modified)
     }
 
+    /// Set the UNIX times when the item was created and modified, for example
+    /// when restoring an item from a backup.
+    #[must_use]
+    pub const fn with_timestamps(mut self, created: Duration, modified: Duration) -> Self {
+        self.created = created.as_secs();
+        self.modified = modified.as_secs();
+        self
+    }
+
     /// Lock the item with the given key.
     pub fn lock(self, key: &Key) -> Result<LockedItem, Error> {
         let inner = self.encrypt(key)?;
@@ -168,24 +181,39 @@ impl UnlockedItem {
     pub(crate) fn encrypt(&self, key: &Key) -> Result<EncryptedItem, Error> {
         key.check_strength()?;
 
-        let iv = crypto::generate_iv()?;
+        let iv = if key.is_aead() {
+            crypto::generate_nonce()?
+        } else {
+            crypto::generate_iv()?
+        };
 
         self.encrypt_inner(key, &iv)
     }
 
     fn encrypt_inner(&self, key: &Key, iv: &[u8]) -> Result<EncryptedItem, Error> {
-        let decrypted = Zeroizing::new(zvariant::to_bytes(*GVARIANT_ENCODING, &self)?.to_vec());
-
-        let mut blob = crypto::encrypt(&*decrypted, key, iv)?;
+        let mut decrypted = Zeroizing::new(zvariant::to_bytes(*GVARIANT_ENCODING, &self)?.to_vec());
+        if key.hides_metadata() {
+            decrypted = pad(&decrypted);
+        }
 
-        blob.extend_from_slice(iv);
-        let mac = crypto::compute_mac(&blob, key)?;
-        blob.extend_from_slice(mac.as_slice());
+        let blob = if key.is_aead() {
+            // The nonce followed by the encrypted data and the tag.
+            let mut blob = iv.to_vec();
+            blob.append(&mut crypto::encrypt_aead(&*decrypted, key, iv)?);
+            blob
+        } else {
+            let mut blob = crypto::encrypt(&*decrypted, key, iv)?;
+            blob.extend_from_slice(iv);
+            let mac = crypto::compute_mac(&blob, key)?;
+            blob.extend_from_slice(mac.as_slice());
+            blob
+        };
 
         let hashed_attributes = self
             .attributes
-            .iter()
-            .filter_map(|(k, v)| Some((k.to_owned(), crypto::compute_mac(v.as_bytes(), key).ok()?)))
+            .hash(key)
+            .into_iter()
+            .filter_map(|(k, v)| Some((k, v.ok()?)))
             .collect();
 
         Ok(EncryptedItem {
@@ -1,7 +1,8 @@
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, atomic::AtomicBool},
+    time::SystemTime,
 };
 
 #[cfg(feature = "async-std")]
@@ -10,6 +11,7 @@ use async_fs as fs;
 use async_lock::{Mutex, RwLock};
 #[cfg(feature = "async-std")]
 use futures_lite::AsyncReadExt;
+use futures_util::{Stream, StreamExt, future::Either};
 #[cfg(feature = "tokio")]
 use tokio::{
     fs,
@@ -19,9 +21,15 @@ use tokio::{
 
 use crate::{
     AsAttributes, Key, Secret,
-    file::{Error, InvalidItemError, LockedItem, LockedKeyring, UnlockedItem, api},
+    file::{
+        Error, InvalidItemError, Kdf, LockedItem, LockedKeyring, UnlockedItem, WeakKeyError, api,
+        counter, index::Index, merge, watch,
+    },
 };
 
+/// How many times merging is attempted before giving up on writing.
+const MAX_MERGE_ATTEMPTS: usize = 5;
+
 /// Definition for batch item creation: (label, attributes, secret, replace)
 pub type ItemDefinition = (String, HashMap<String, String>, Secret, bool);
 
@@ -35,6 +43,19 @@ pub struct UnlockedKeyring {
     pub(super) mtime: Mutex<Option<std::time::SystemTime>>,
     pub(super) key: Mutex<Option<Arc<Key>>>,
     pub(super) secret: Mutex<Arc<Secret>>,
+    /// The items as of the last read or write of the file, the base of the
+    /// merges, encrypted with the current key
+    pub(super) synced_items: Mutex<Vec<api::EncryptedItem>>,
+    /// The positions of the items by hashed attribute, always locked after
+    /// the keyring and updated along with it
+    pub(super) index: RwLock<Index>,
+    pub(super) merge_on_conflict: bool,
+    /// The rollback counter, stored outside the file
+    pub(super) counter: Option<PathBuf>,
+    /// Whether the secret was not validated when unlocking, the file is then
+    /// written without its integrity MAC if it holds items encrypted with
+    /// another secret
+    pub(super) unchecked: bool,
 }
 
 impl UnlockedKeyring {
@@ -60,7 +81,8 @@ impl UnlockedKeyring {
     ///
     /// The secret is not validated to be the correct one to decrypt the keyring
     /// items. Allowing the API user to write new items with a different
-    /// secret on top of previously added items with a different secret.
+    /// secret on top of previously added items with a different secret. The
+    /// file is then written without its integrity MAC.
     ///
     /// As it is not a supported behaviour, this API is mostly meant for
     /// recovering broken keyrings.
@@ -102,6 +124,11 @@ impl UnlockedKeyring {
             mtime: Default::default(),
             key: Default::default(),
             secret: Mutex::new(Arc::new(secret)),
+            synced_items: Default::default(),
+            index: Default::default(),
+            merge_on_conflict: false,
+            counter: None,
+            unchecked: false,
         })
     }
 
@@ -117,11 +144,16 @@ impl UnlockedKeyring {
 
         match api::Keyring::try_from(content.as_slice()) {
             Ok(keyring) => Ok(Self {
+                index: RwLock::new(Index::new(&keyring.items)),
                 keyring: Arc::new(RwLock::new(keyring)),
                 path: Some(path.as_ref().to_path_buf()),
                 mtime: Default::default(),
                 key: Default::default(),
                 secret: Mutex::new(Arc::new(secret)),
+                synced_items: Default::default(),
+                merge_on_conflict: false,
+                counter: None,
+                unchecked: false,
             }),
             Err(Error::VersionMismatch(Some(version)))
                 if version[0] == api::LEGACY_MAJOR_VERSION =>
@@ -145,11 +177,16 @@ impl UnlockedKeyring {
                 }
 
                 Ok(Self {
+                    index: RwLock::new(Index::new(&keyring.items)),
                     keyring: Arc::new(RwLock::new(keyring)),
                     path: Some(path.as_ref().to_path_buf()),
                     mtime: Default::default(),
                     key: Default::default(),
                     secret: Mutex::new(Arc::new(secret)),
+                    synced_items: Default::default(),
+                    merge_on_conflict: false,
+                    counter: None,
+                    unchecked: false,
                 })
             }
             Err(err) => Err(err),
@@ -191,6 +228,11 @@ impl UnlockedKeyring {
                 mtime: Default::default(),
                 key: Default::default(),
                 secret: Mutex::new(Arc::new(secret)),
+                synced_items: Default::default(),
+                index: Default::default(),
+                merge_on_conflict: false,
+                counter: None,
+                unchecked: false,
             })
         }
     }
@@ -201,9 +243,33 @@ impl UnlockedKeyring {
             keyring: self.keyring,
             path: self.path,
             mtime: self.mtime,
+            merge_on_conflict: self.merge_on_conflict,
+            counter: self.counter,
+            // The content in memory was not read from the file
+            check_integrity: AtomicBool::new(false),
         }
     }
 
+    /// Merge the changes written to the file by another process, instead of
+    /// failing with [`Error::TargetFileChanged`].
+    ///
+    /// The items are identified by their attributes. When both processes
+    /// modified the same item, the last modification is kept. Disabled by
+    /// default.
+    pub fn with_merge_on_conflict(mut self, merge_on_conflict: bool) -> Self {
+        self.merge_on_conflict = merge_on_conflict;
+        self
+    }
+
+    /// Store the number of times the file was written in a counter at
+    /// `path`, outside of the keyring file.
+    ///
+    /// See [`LockedKeyring::with_rollback_counter`].
+    pub fn with_rollback_counter(mut self, path: impl AsRef<Path>) -> Self {
+        self.counter = Some(path.as_ref().to_path_buf());
+        self
+    }
+
     /// Lock an item using the keyring's key.
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, item)))]
     pub async fn lock_item(&self, item: UnlockedItem) -> Result<LockedItem, Error> {
@@ -291,7 +357,14 @@ impl UnlockedKeyring {
     ) -> Result<Vec<UnlockedItem>, Error> {
         let key = self.derive_key().await?;
         let keyring = self.keyring.read().await;
-        let results = keyring.search_items(attributes, &key)?;
+        let results = self
+            .index
+            .read()
+            .await
+            .search(&attributes.hash(&key))
+            .into_iter()
+            .map(|position| keyring.items[position].clone().decrypt(&key))
+            .collect::<Result<Vec<_>, _>>()?;
 
         #[cfg(feature = "tracing")]
         tracing::debug!("Found {} matching items", results.len());
@@ -307,8 +380,17 @@ impl UnlockedKeyring {
     ) -> Result<Option<UnlockedItem>, Error> {
         let key = self.derive_key().await?;
         let keyring = self.keyring.read().await;
-
-        keyring.lookup_item(attributes, &key)
+        let position = self
+            .index
+            .read()
+            .await
+            .search(&attributes.hash(&key))
+            .first()
+            .copied();
+
+        position
+            .map(|position| keyring.items[position].clone().decrypt(&key))
+            .transpose()
     }
 
     /// Find the index in the list of items of the first item matching the
@@ -319,9 +401,16 @@ impl UnlockedKeyring {
         attributes: &impl AsAttributes,
     ) -> Result<Option<usize>, Error> {
         let key = self.derive_key().await?;
-        let keyring = self.keyring.read().await;
-
-        Ok(keyring.lookup_item_index(attributes, &key))
+        // The index is updated along with the items, under the keyring lock
+        let _keyring = self.keyring.read().await;
+
+        Ok(self
+            .index
+            .read()
+            .await
+            .search(&attributes.hash(&key))
+            .first()
+            .copied())
     }
 
     /// Delete an item.
@@ -333,7 +422,8 @@ impl UnlockedKeyring {
         {
             let key = self.derive_key().await?;
             let mut keyring = self.keyring.write().await;
-            keyring.remove_items(attributes, &key)?;
+            let mut index = self.index.write().await;
+            Self::remove_items(&mut keyring, &mut index, attributes, &key)?;
         };
 
         self.write().await?;
@@ -348,6 +438,28 @@ impl UnlockedKeyring {
         Ok(())
     }
 
+    /// Remove the items matching `attributes` from `keyring`, found using
+    /// its `index`.
+    ///
+    /// Nothing is removed if any of them cannot be decrypted.
+    fn remove_items(
+        keyring: &mut api::Keyring,
+        index: &mut Index,
+        attributes: &impl AsAttributes,
+        key: &Key,
+    ) -> Result<(), Error> {
+        let positions = index.search(&attributes.hash(key));
+        // Validate items to be removed before actually removing them
+        if positions
+            .iter()
+            .any(|position| !keyring.items[*position].is_valid(key))
+        {
+            return Err(Error::MacError);
+        }
+        index.remove(&mut keyring.items, &positions);
+        Ok(())
+    }
+
     /// Create a new item
     ///
     /// # Arguments
@@ -369,12 +481,13 @@ impl UnlockedKeyring {
         let item = {
             let key = self.derive_key().await?;
             let mut keyring = self.keyring.write().await;
+            let mut index = self.index.write().await;
             if replace {
-                keyring.remove_items(attributes, &key)?;
+                Self::remove_items(&mut keyring, &mut index, attributes, &key)?;
             }
             let item = UnlockedItem::new(label, attributes, secret);
             let encrypted_item = item.encrypt(&key)?;
-            keyring.items.push(encrypted_item);
+            index.push(&mut keyring.items, encrypted_item);
             item
         };
         match self.write().await {
@@ -402,11 +515,14 @@ impl UnlockedKeyring {
             let key = self.derive_key().await?;
             let mut keyring = self.keyring.write().await;
 
-            if let Some(item_store) = keyring.items.get_mut(index) {
-                *item_store = item.encrypt(&key)?;
-            } else {
+            if index >= keyring.items.len() {
                 return Err(Error::InvalidItemIndex(index));
             }
+            let encrypted_item = item.encrypt(&key)?;
+            self.index
+                .write()
+                .await
+                .replace(&mut keyring.items, index, encrypted_item)?;
         }
         self.write().await
     }
@@ -422,7 +538,10 @@ impl UnlockedKeyring {
             let mut keyring = self.keyring.write().await;
 
             if index < keyring.items.len() {
-                keyring.items.remove(index);
+                self.index
+                    .write()
+                    .await
+                    .remove(&mut keyring.items, &[index]);
             } else {
                 return Err(Error::InvalidItemIndex(index));
             }
@@ -439,52 +558,415 @@ impl UnlockedKeyring {
         let key = self.derive_key().await?;
         let mut mtime = self.mtime.lock().await;
         let mut keyring = self.keyring.write().await;
+        let mut index = self.index.write().await;
 
         #[cfg(feature = "tracing")]
         let _span = tracing::debug_span!("bulk_create", items_to_create = items.len());
 
         for (label, attributes, secret, replace) in items {
             if replace {
-                keyring.remove_items(&attributes, &key)?;
+                Self::remove_items(&mut keyring, &mut index, &attributes, &key)?;
             }
             let item = UnlockedItem::new(label, &attributes, secret);
             let encrypted_item = item.encrypt(&key)?;
-            keyring.items.push(encrypted_item);
+            index.push(&mut keyring.items, encrypted_item);
         }
+        drop(index);
 
         #[cfg(feature = "tracing")]
         tracing::debug!("Writing keyring back to the file");
-        if let Some(ref path) = self.path {
-            keyring.dump(path, *mtime).await?;
-            // Update mtime after successful write
-            if let Ok(modified) = fs::metadata(path).await?.modified() {
-                *mtime = Some(modified);
+        self.dump(&mut keyring, &mut mtime, &key).await
+    }
+
+    /// Insert multiple items in a single operation, keeping their creation
+    /// and modification times.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The items to insert.
+    /// * `replace` - Whether to replace the existing items with the same
+    ///   attributes.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, items), fields(item_count = items.len(), replace = replace)))]
+    pub async fn insert_items(&self, items: Vec<UnlockedItem>, replace: bool) -> Result<(), Error> {
+        let key = self.derive_key().await?;
+        let mut mtime = self.mtime.lock().await;
+        let mut keyring = self.keyring.write().await;
+        let mut index = self.index.write().await;
+
+        for item in items {
+            if replace {
+                Self::remove_items(&mut keyring, &mut index, item.attributes(), &key)?;
             }
+            let encrypted_item = item.encrypt(&key)?;
+            index.push(&mut keyring.items, encrypted_item);
         }
-        Ok(())
+        drop(index);
+
+        self.dump(&mut keyring, &mut mtime, &key).await
     }
 
     /// Write the changes to the keyring file.
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
     pub async fn write(&self) -> Result<(), Error> {
+        if self.path.is_none() {
+            return Ok(());
+        }
+        // Derived before locking the keyring, which is needed to derive it
+        let key = self.derive_key().await?;
         let mut mtime = self.mtime.lock().await;
-        {
-            let mut keyring = self.keyring.write().await;
+        let mut key_lock = self.key.lock().await;
+        let mut keyring = self.keyring.write().await;
+        let key = if let Kdf::Pbkdf2 { .. } = keyring.kdf() {
+            match self.upgrade(&mut keyring, &key).await? {
+                Some(upgraded_key) => {
+                    *key_lock = Some(Arc::clone(&upgraded_key));
+                    upgraded_key
+                }
+                None => key,
+            }
+        } else {
+            key
+        };
+        drop(key_lock);
+        self.dump(&mut keyring, &mut mtime, &key).await
+    }
 
-            if let Some(ref path) = self.path {
-                keyring.dump(path, *mtime).await?;
+    /// Upgrade `keyring` to the v2 format, re-encrypting its items with a key
+    /// derived using Argon2id.
+    ///
+    /// Returns the new key, or `None` if the keyring was kept as is because
+    /// some of its items cannot be decrypted with `key` or the secret is too
+    /// weak.
+    async fn upgrade(
+        &self,
+        keyring: &mut api::Keyring,
+        key: &Key,
+    ) -> Result<Option<Arc<Key>>, Error> {
+        let Ok(items) = keyring
+            .items
+            .iter()
+            .map(|item| item.clone().decrypt(key))
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("Not upgrading a keyring with broken items");
+            return Ok(None);
+        };
+
+        let upgraded = keyring.rekeyed(Kdf::default(), false)?;
+        match self.encrypt_items(upgraded, &items).await {
+            Ok((upgraded, upgraded_key)) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("Upgrading keyring to the v2 format");
+                *keyring = upgraded;
+                *self.index.write().await = Index::new(&keyring.items);
+                // The base of the merges is encrypted with the new key too
+                let mut synced_items = self.synced_items.lock().await;
+                *synced_items = std::mem::take(&mut *synced_items)
+                    .into_iter()
+                    .map(|item| match item.clone().decrypt(key) {
+                        Ok(item) => item.encrypt(&upgraded_key),
+                        Err(_) => Ok(item),
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(Some(Arc::new(upgraded_key)))
+            }
+            Err(Error::WeakKey(_)) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("Not upgrading a keyring with a weak secret");
+                Ok(None)
             }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Derive the key of the empty `keyring` and add the `items` to it,
+    /// encrypted with this key.
+    async fn encrypt_items(
+        &self,
+        keyring: api::Keyring,
+        items: &[UnlockedItem],
+    ) -> Result<(api::Keyring, Key), Error> {
+        let secret = Arc::clone(&*self.secret.lock().await);
+        #[cfg(feature = "async-std")]
+        let (key, mut keyring) = blocking::unblock(move || {
+            let key = keyring.derive_key(&secret);
+            (key, keyring)
+        })
+        .await;
+        #[cfg(feature = "tokio")]
+        let (key, mut keyring) = tokio::task::spawn_blocking(move || {
+            let key = keyring.derive_key(&secret);
+            (key, keyring)
+        })
+        .await
+        .unwrap();
+        let key = key?;
+        key.check_strength()?;
+
+        keyring.items = items
+            .iter()
+            .map(|item| item.encrypt(&key))
+            .collect::<Result<_, _>>()?;
+        Ok((keyring, key))
+    }
+
+    /// Derive a new key using `kdf` and a new salt, and re-encrypt all the
+    /// items with it.
+    ///
+    /// The keyring is left untouched if any of the items cannot be decrypted
+    /// or the file cannot be written. PBKDF2 is only supported for reading v1
+    /// files, [`Kdf::Argon2id`] is required.
+    ///
+    /// # Arguments
+    ///
+    /// * `kdf` - The key derivation function and its parameters.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
+    pub async fn rekey(&self, kdf: Kdf) -> Result<(), Error> {
+        if let Kdf::Pbkdf2 { .. } = kdf {
+            return Err(WeakKeyError::KdfObsolete.into());
+        }
+        self.rekey_inner(Some(kdf), None).await
+    }
+
+    /// Hide the names of the attributes, in addition to their values, and pad
+    /// the items so that the length of their secret isn't revealed either.
+    ///
+    /// Searching for items keeps working the same way. The items are
+    /// re-encrypted with a key derived from a new salt, and v1 keyrings are
+    /// upgraded to the v2 format, which is required. The keyring is left
+    /// untouched if any of the items cannot be decrypted or the file cannot
+    /// be written. Disabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `hidden_metadata` - Whether to hide the metadata of the items.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
+    pub async fn set_hidden_metadata(&self, hidden_metadata: bool) -> Result<(), Error> {
+        self.rekey_inner(None, Some(hidden_metadata)).await
+    }
+
+    /// Whether the metadata of the items is hidden.
+    pub async fn hidden_metadata(&self) -> bool {
+        self.keyring.read().await.hidden_metadata()
+    }
+
+    /// Re-encrypt all the items with a key derived using `kdf`, or the current
+    /// Argon2id parameters, hiding their metadata if `hidden_metadata` is set
+    /// or it already was.
+    async fn rekey_inner(
+        &self,
+        kdf: Option<Kdf>,
+        hidden_metadata: Option<bool>,
+    ) -> Result<(), Error> {
+        // Derived before locking the keyring, which is needed to derive it
+        let key = self.derive_key().await?;
+        let mut mtime = self.mtime.lock().await;
+        let mut key_lock = self.key.lock().await;
+        let mut keyring = self.keyring.write().await;
+
+        let items = keyring
+            .items
+            .iter()
+            .map(|item| item.clone().decrypt(&key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let kdf = match (kdf, keyring.kdf()) {
+            (Some(kdf), _) => kdf,
+            (None, Kdf::Pbkdf2 { .. }) => Kdf::default(),
+            (None, kdf) => kdf,
         };
+        let hidden_metadata = hidden_metadata.unwrap_or_else(|| keyring.hidden_metadata());
+        let rekeyed = keyring.rekeyed(kdf, hidden_metadata)?;
+        rekeyed.parameters_strength()?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!("Re-encrypting {} items", items.len());
+        let (mut rekeyed, rekeyed_key) = self.encrypt_items(rekeyed, &items).await?;
+        if let Some(ref path) = self.path {
+            rekeyed.dump(path, *mtime, &rekeyed_key).await?;
+        }
+
+        *keyring = rekeyed;
+        *self.index.write().await = Index::new(&keyring.items);
+        *key_lock = Some(Arc::new(rekeyed_key));
+        drop(key_lock);
+        self.synced(&keyring, &mut mtime).await
+    }
+
+    /// The key derivation function and its parameters.
+    pub async fn kdf(&self) -> Kdf {
+        self.keyring.read().await.kdf()
+    }
+
+    /// Write `keyring` to the file, merging the changes written since `mtime`
+    /// if enabled.
+    async fn dump(
+        &self,
+        keyring: &mut api::Keyring,
+        mtime: &mut Option<SystemTime>,
+        key: &Key,
+    ) -> Result<(), Error> {
         let Some(ref path) = self.path else {
             return Ok(());
         };
+        let mut n_attempts = 0;
+        loop {
+            // The integrity MAC of a file mixing items encrypted with different
+            // secrets cannot be verified by any of them
+            let result = if self.unchecked && keyring.items.iter().any(|item| !item.is_valid(key)) {
+                keyring.dump_unauthenticated(path, *mtime).await
+            } else {
+                keyring.dump(path, *mtime, key).await
+            };
+            let Err(err) = result else {
+                break;
+            };
+            if !matches!(err, Error::TargetFileChanged(_))
+                || !self.merge_on_conflict
+                || n_attempts == MAX_MERGE_ATTEMPTS
+            {
+                return Err(err);
+            }
+            n_attempts += 1;
+            #[cfg(feature = "tracing")]
+            tracing::debug!("Merging the changes written to {:?}", path);
+            self.merge(keyring, path, mtime, key).await?;
+        }
+        self.synced(keyring, mtime).await
+    }
+
+    /// Merge the items of the file at `path` into `keyring`.
+    async fn merge(
+        &self,
+        keyring: &mut api::Keyring,
+        path: &Path,
+        mtime: &mut Option<SystemTime>,
+        key: &Key,
+    ) -> Result<(), Error> {
+        let (remote_items, modified) = match watch::read(path, None).await? {
+            Some((remote, modified)) if keyring.has_same_key_parameters(&remote) => {
+                self.verify(&remote, key).await?;
+                (remote.items, modified)
+            }
+            Some((remote, modified)) => {
+                // Written with a different salt, by a new keyring for example
+                let secret = Arc::clone(&*self.secret.lock().await);
+                let remote_key = remote.derive_key(&secret)?;
+                self.verify(&remote, &remote_key).await?;
+                (merge::reencrypt(remote.items, &remote_key, key)?, modified)
+            }
+            None => {
+                // Removed in the meantime, there are no changes to merge
+                *mtime = None;
+                return Ok(());
+            }
+        };
+        let local_items = std::mem::take(&mut keyring.items);
+        keyring.items = merge::merge(
+            &self.synced_items.lock().await,
+            local_items,
+            remote_items,
+            key,
+        );
+        *self.index.write().await = Index::new(&keyring.items);
+        *mtime = modified;
+        Ok(())
+    }
+
+    /// Check the integrity MAC of `remote`, read from the file, with `key`,
+    /// and that it isn't older than the last file written, before using its
+    /// items.
+    ///
+    /// The integrity MAC is not checked if the secret was not validated.
+    async fn verify(&self, remote: &api::Keyring, key: &Key) -> Result<(), Error> {
+        if !self.unchecked {
+            remote.verify_integrity(key)?;
+        }
+        if let Some(ref counter) = self.counter {
+            counter::check(counter, remote.usage_count(), remote.is_authenticated()).await?;
+        }
+        Ok(())
+    }
 
-        if let Ok(modified) = fs::metadata(path).await?.modified() {
+    /// Remember `keyring` was written to the file.
+    async fn synced(
+        &self,
+        keyring: &api::Keyring,
+        mtime: &mut Option<SystemTime>,
+    ) -> Result<(), Error> {
+        if let Some(ref path) = self.path
+            && let Ok(modified) = fs::metadata(path).await?.modified()
+        {
             *mtime = Some(modified);
         }
+        if let Some(ref counter) = self.counter {
+            counter::update(counter, keyring.usage_count()).await?;
+        }
+        *self.synced_items.lock().await = keyring.items.clone();
         Ok(())
     }
 
+    /// Reload the keyring if its file was written since it was read, by
+    /// another process for example.
+    ///
+    /// Returns whether the keyring was reloaded.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
+    pub async fn reload(&self) -> Result<bool, Error> {
+        let Some(ref path) = self.path else {
+            return Ok(false);
+        };
+        // Derived before locking the keyring, which is needed to derive it
+        let key = self.derive_key().await?;
+        let mut mtime = self.mtime.lock().await;
+        let Some((new_keyring, modified)) = watch::read(path, *mtime).await? else {
+            return Ok(false);
+        };
+        let mut keyring = self.keyring.write().await;
+        let has_same_key = keyring.has_same_key_parameters(&new_keyring);
+        if has_same_key {
+            self.verify(&new_keyring, &key).await?;
+        } else {
+            let secret = Arc::clone(&*self.secret.lock().await);
+            self.verify(&new_keyring, &new_keyring.derive_key(&secret)?)
+                .await?;
+        }
+        *keyring = new_keyring;
+        *mtime = modified;
+        *self.index.write().await = Index::new(&keyring.items);
+        *self.synced_items.lock().await = keyring.items.clone();
+        drop(keyring);
+        if !has_same_key {
+            // The file was written with a different salt, the key has to be
+            // derived again
+            *self.key.lock().await = None;
+        }
+        Ok(true)
+    }
+
+    /// Receive a notification every time the keyring file is written by
+    /// another process.
+    ///
+    /// The keyring is [reloaded](Self::reload) before the notification is
+    /// received, so the following writes don't fail with
+    /// [`Error::TargetFileChanged`].
+    pub fn receive_changed(&self) -> Result<impl Stream<Item = ()> + '_, Error> {
+        let changes = match self.path {
+            Some(ref path) => Either::Left(watch::watch(path)?),
+            // Nobody else can write a temporary keyring
+            None => Either::Right(futures_util::stream::pending()),
+        };
+        Ok(changes.filter_map(move |()| async move {
+            match self.reload().await {
+                Ok(is_reloaded) => is_reloaded.then_some(()),
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!("Failed to reload the keyring file: {_err}");
+                    None
+                }
+            }
+        }))
+    }
+
     /// Return key, derive and store it first if not initialized
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
     async fn derive_key(&self) -> Result<Arc<Key>, crate::crypto::Error> {
@@ -548,6 +1030,7 @@ impl UnlockedKeyring {
         // Reset Keyring content before setting the new key
         let mut keyring = self.keyring.write().await;
         keyring.reset()?;
+        *self.index.write().await = Index::default();
         drop(keyring);
 
         // Set new key
@@ -557,10 +1040,12 @@ impl UnlockedKeyring {
         let _reencrypt_span = tracing::debug_span!("reencrypt", total_items = items.len());
 
         let mut keyring = self.keyring.write().await;
+        let mut index = self.index.write().await;
         for item in items {
             let encrypted_item = item.encrypt(&key)?;
-            keyring.items.push(encrypted_item);
+            index.push(&mut keyring.items, encrypted_item);
         }
+        drop(index);
         drop(keyring);
 
         self.write().await
@@ -580,6 +1065,34 @@ impl UnlockedKeyring {
         Ok(keyring.validate_secret(secret)?)
     }
 
+    /// Write the items that cannot be decrypted with the key associated to the
+    /// keyring to a new keyring file at `path`.
+    ///
+    /// The new file uses the same key derivation parameters, so the items can
+    /// be recovered later on with the secret they were created with. It is
+    /// written without an integrity MAC, which would require that secret, it
+    /// has to be loaded with [`Self::load_unchecked`] until it is written
+    /// again with that secret. Returns the number of written items.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, path)))]
+    pub async fn dump_broken_items(&self, path: impl AsRef<Path>) -> Result<usize, Error> {
+        let key = self.derive_key().await?;
+        let keyring = self.keyring.read().await;
+
+        let broken_items = keyring
+            .items
+            .iter()
+            .filter(|encrypted_item| !encrypted_item.is_valid(&key))
+            .cloned()
+            .collect::<Vec<_>>();
+        let n_broken_items = broken_items.len();
+
+        let mut broken_keyring = keyring.with_items(broken_items);
+        drop(keyring);
+
+        broken_keyring.dump_unauthenticated(path, None).await?;
+        Ok(n_broken_items)
+    }
+
     /// Delete any item that cannot be decrypted with the key associated to the
     /// keyring.
     ///
@@ -608,9 +1121,10 @@ impl UnlockedKeyring {
         #[cfg(feature = "tracing")]
         let _remove_span = tracing::debug_span!("remove_broken", broken_count = n_broken_items);
 
-        for index in broken_items.into_iter().rev() {
-            keyring.items.remove(index);
-        }
+        self.index
+            .write()
+            .await
+            .remove(&mut keyring.items, &broken_items);
         drop(keyring);
 
         self.write().await?;
@@ -0,0 +1,127 @@
+//! Detect the changes made to a keyring file by other processes.
+
+use std::{
+    ffi::OsString,
+    mem::MaybeUninit,
+    os::{fd::AsFd, unix::ffi::OsStrExt},
+    path::Path,
+    time::SystemTime,
+};
+
+#[cfg(feature = "async-std")]
+use async_fs as fs;
+#[cfg(feature = "async-std")]
+use futures_lite::AsyncReadExt;
+use futures_util::Stream;
+use rustix::{
+    fd::OwnedFd,
+    fs::inotify::{self, CreateFlags, WatchFlags},
+    io::Errno,
+};
+#[cfg(feature = "tokio")]
+use tokio::{fs, io::AsyncReadExt};
+
+use super::{Error, api};
+
+#[cfg(feature = "tokio")]
+type AsyncFd = tokio::io::unix::AsyncFd<OwnedFd>;
+#[cfg(feature = "async-std")]
+type AsyncFd = async_io::Async<OwnedFd>;
+
+/// Watch the writes to the file at `path`.
+///
+/// The parent directory is watched, as the file is replaced on every write.
+pub(crate) fn watch(path: &Path) -> Result<impl Stream<Item = ()> + use<>, Error> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| Error::NoParentDir(path.display().to_string()))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::NoParentDir(path.display().to_string()))?
+        .to_owned();
+
+    let fd = inotify::init(CreateFlags::CLOEXEC | CreateFlags::NONBLOCK)
+        .map_err(std::io::Error::from)?;
+    inotify::add_watch(&fd, parent, WatchFlags::CLOSE_WRITE | WatchFlags::MOVED_TO)
+        .map_err(std::io::Error::from)?;
+    let fd = AsyncFd::new(fd)?;
+
+    Ok(futures_util::stream::unfold(
+        (fd, file_name),
+        |(fd, file_name)| async move {
+            loop {
+                match next_change(&fd, &file_name).await {
+                    Ok(true) => return Some(((), (fd, file_name))),
+                    Ok(false) => continue,
+                    Err(_err) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::error!("Failed to watch the keyring file: {_err}");
+                        return None;
+                    }
+                }
+            }
+        },
+    ))
+}
+
+/// Wait for the next events, and return whether the file was written.
+async fn next_change(fd: &AsyncFd, file_name: &OsString) -> std::io::Result<bool> {
+    #[cfg(feature = "tokio")]
+    {
+        let mut guard = fd.readable().await?;
+        let changed = read_events(fd.get_ref(), file_name)?;
+        guard.clear_ready();
+        Ok(changed)
+    }
+    #[cfg(feature = "async-std")]
+    {
+        fd.readable().await?;
+        read_events(fd.get_ref(), file_name)
+    }
+}
+
+/// Read all the pending events.
+fn read_events(fd: impl AsFd, file_name: &OsString) -> std::io::Result<bool> {
+    let mut buf = [MaybeUninit::uninit(); 4096];
+    let mut reader = inotify::Reader::new(fd, &mut buf);
+    let mut changed = false;
+    loop {
+        match reader.next() {
+            Ok(event) => {
+                changed |= event
+                    .file_name()
+                    .is_some_and(|name| name.to_bytes() == file_name.as_bytes());
+            }
+            Err(Errno::WOULDBLOCK) => return Ok(changed),
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Read the keyring file at `path` and its modification time, unless it
+/// wasn't written since `mtime`.
+///
+/// `mtime` is expected to be locked by the caller, to not race with a write.
+/// Returns `None` if the file doesn't exist or is unchanged.
+pub(super) async fn read(
+    path: &Path,
+    mtime: Option<SystemTime>,
+) -> Result<Option<(api::Keyring, Option<SystemTime>)>, Error> {
+    let mut file = match fs::File::open(path).await {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+        Ok(file) => file,
+    };
+    let metadata = file.metadata().await?;
+    let modified = metadata.modified().ok();
+    if mtime.is_some() && modified == mtime {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!("Reading the keyring file {:?}", path);
+    let mut content = Vec::with_capacity(metadata.len() as usize);
+    file.read_to_end(&mut content).await?;
+    let keyring = api::Keyring::try_from(content.as_slice())?;
+    Ok(Some((keyring, modified)))
+}
@@ -1,13 +1,30 @@
+use std::sync::OnceLock;
+
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::{crypto, file};
 
+/// The HKDF info of the key used for computing the MAC of the attribute names.
+const ATTRIBUTE_NAME_INFO: &[u8] = b"oo7 attribute names";
+
 /// A key.
 #[derive(Zeroize, ZeroizeOnDrop)]
 pub struct Key {
     key: Vec<u8>,
     #[zeroize(skip)]
     strength: Result<(), file::WeakKeyError>,
+    /// Whether the key is made of an AEAD key followed by a MAC key, as used by
+    /// the v2 file format
+    #[zeroize(skip)]
+    is_aead: bool,
+    /// Whether the attribute names are hashed and the item plaintexts padded,
+    /// as done by the v2 file format when hiding the metadata
+    #[zeroize(skip)]
+    hides_metadata: bool,
+    /// The key of the MACs of the attribute names, derived on first use and
+    /// zeroized when dropped
+    #[zeroize(skip)]
+    name_key: OnceLock<Box<Key>>,
 }
 
 impl std::fmt::Debug for Key {
@@ -28,6 +45,8 @@ impl AsRef<[u8]> for Key {
 
 impl AsMut<[u8]> for Key {
     fn as_mut(&mut self) -> &mut [u8] {
+        // Derived from the previous key otherwise
+        self.name_key.take();
         &mut self.key
     }
 }
@@ -45,7 +64,81 @@ impl Key {
         key: Vec<u8>,
         strength: Result<(), file::WeakKeyError>,
     ) -> Self {
-        Self { key, strength }
+        Self {
+            key,
+            strength,
+            is_aead: false,
+            hides_metadata: false,
+            name_key: OnceLock::new(),
+        }
+    }
+
+    pub(crate) const fn new_aead_with_strength(
+        key: Vec<u8>,
+        strength: Result<(), file::WeakKeyError>,
+    ) -> Self {
+        Self {
+            key,
+            strength,
+            is_aead: true,
+            hides_metadata: false,
+            name_key: OnceLock::new(),
+        }
+    }
+
+    pub(crate) const fn is_aead(&self) -> bool {
+        self.is_aead
+    }
+
+    pub(crate) const fn hides_metadata(&self) -> bool {
+        self.hides_metadata
+    }
+
+    /// Hide the metadata of the items encrypted with this AEAD key.
+    pub(crate) fn with_hidden_metadata(mut self, hides_metadata: bool) -> Self {
+        self.hides_metadata = self.is_aead && hides_metadata;
+        self
+    }
+
+    /// The name an attribute is stored under.
+    ///
+    /// When hiding the metadata, this is the hex encoded MAC of the name,
+    /// computed with a key derived for this purpose so that it never matches
+    /// the MAC of a value.
+    pub(crate) fn attribute_name(&self, name: &str) -> Result<String, crypto::Error> {
+        if !self.hides_metadata {
+            return Ok(name.to_owned());
+        }
+        let name_key = match self.name_key.get() {
+            Some(name_key) => name_key,
+            None => {
+                let name_key = crypto::derive_mac_key(self, ATTRIBUTE_NAME_INFO)?;
+                self.name_key.get_or_init(|| Box::new(name_key))
+            }
+        };
+        let mac = crypto::compute_mac(name, name_key)?;
+        Ok(mac.as_slice().iter().fold(String::new(), |mut acc, b| {
+            acc.push_str(&format!("{b:02x}"));
+            acc
+        }))
+    }
+
+    /// The part of the key used for encrypting.
+    pub(crate) fn encryption_key(&self) -> &[u8] {
+        if self.is_aead {
+            &self.key[..self.key.len() / 2]
+        } else {
+            &self.key
+        }
+    }
+
+    /// The part of the key used for computing MACs.
+    pub(crate) fn mac_key(&self) -> &[u8] {
+        if self.is_aead {
+            &self.key[self.key.len() / 2..]
+        } else {
+            &self.key
+        }
     }
 
     pub fn generate_private_key() -> Result<Self, crypto::Error> {
@@ -149,4 +242,21 @@ mod tests {
         assert!(debug_output.contains("key: [REDACTED]"));
         assert!(debug_output.contains("strength:"));
     }
+
+    #[test]
+    fn attribute_name() {
+        let mut key = Key::new_aead_with_strength(vec![1; 64], Ok(())).with_hidden_metadata(true);
+        let name = key.attribute_name("name").unwrap();
+        assert_eq!(name.len(), 64);
+        assert!(key.name_key.get().is_some());
+        assert_eq!(key.attribute_name("name").unwrap(), name);
+
+        // Derived again from the new key
+        key.as_mut().fill(2);
+        assert_ne!(key.attribute_name("name").unwrap(), name);
+
+        let key = Key::new_aead_with_strength(vec![1; 64], Ok(()));
+        assert_eq!(key.attribute_name("name").unwrap(), "name");
+        assert!(key.name_key.get().is_none());
+    }
 }
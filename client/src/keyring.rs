@@ -1,11 +1,12 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
 
 #[cfg(feature = "async-std")]
 use async_lock::RwLock;
+use futures_util::{Stream, StreamExt, stream};
 #[cfg(feature = "tokio")]
 use tokio::sync::RwLock;
 
-use crate::{AsAttributes, Result, Secret, dbus, file};
+use crate::{AsAttributes, Error, Result, Secret, dbus, file};
 
 /// A [Secret Service](crate::dbus) or [file](crate::file) backed keyring
 /// implementation.
@@ -22,57 +23,31 @@ pub enum Keyring {
     File(Arc<RwLock<Option<file::Keyring>>>),
     #[doc(hidden)]
     DBus(dbus::Collection),
+    #[cfg(feature = "mock")]
+    #[doc(hidden)]
+    Mock(crate::mock::Keyring),
 }
 
 impl Keyring {
     /// Create a new instance of the Keyring.
+    ///
+    /// The backend is picked automatically, see [`KeyringBuilder`] to select
+    /// it explicitly.
     pub async fn new() -> Result<Self> {
-        if ashpd::is_sandboxed() {
-            #[cfg(feature = "tracing")]
-            tracing::debug!("Application is sandboxed, using the file backend");
+        KeyringBuilder::new().build().await
+    }
 
-            let secret = Secret::from(
-                ashpd::desktop::secret::retrieve()
-                    .await
-                    .map_err(crate::file::Error::from)?,
-            );
-            match file::UnlockedKeyring::load(
-                crate::file::api::Keyring::default_path()?,
-                secret.clone(),
-            )
-            .await
-            {
-                Ok(file) => {
-                    return Ok(Self::File(Arc::new(RwLock::new(Some(
-                        file::Keyring::Unlocked(file),
-                    )))));
-                }
-                // Do nothing in this case, we are supposed to fallback to the host keyring
-                Err(super::file::Error::Portal(ashpd::Error::PortalNotFound(_))) => {
-                    #[cfg(feature = "tracing")]
-                    tracing::debug!(
-                        "org.freedesktop.portal.Secrets is not available, falling back to the Secret Service backend"
-                    );
-                }
-                Err(e) => {
-                    return Err(crate::Error::File(e));
-                }
-            };
-        } else {
-            #[cfg(feature = "tracing")]
-            tracing::debug!(
-                "Application is not sandboxed, falling back to the Secret Service backend"
-            );
-        }
-        let service = dbus::Service::new().await?;
-        let collection = service.default_collection().await?;
-        Ok(Self::DBus(collection))
+    /// Create a [`KeyringBuilder`].
+    pub fn builder() -> KeyringBuilder {
+        KeyringBuilder::new()
     }
 
     /// Unlock the used collection.
     pub async fn unlock(&self) -> Result<()> {
         match self {
             Self::DBus(backend) => backend.unlock(None).await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(backend) => backend.unlock()?,
             Self::File(keyring) => {
                 let mut kg = keyring.write().await;
                 let kg_value = kg.take();
@@ -101,6 +76,8 @@ impl Keyring {
     pub async fn lock(&self) -> Result<()> {
         match self {
             Self::DBus(backend) => backend.lock(None).await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(backend) => backend.lock()?,
             Self::File(keyring) => {
                 let mut kg = keyring.write().await;
                 let kg_value = kg.take();
@@ -122,6 +99,8 @@ impl Keyring {
     pub async fn is_locked(&self) -> Result<bool> {
         match self {
             Self::DBus(collection) => collection.is_locked().await.map_err(From::from),
+            #[cfg(feature = "mock")]
+            Self::Mock(backend) => Ok(backend.is_locked()),
             Self::File(keyring) => {
                 let keyring_guard = keyring.read().await;
                 Ok(keyring_guard
@@ -141,6 +120,8 @@ impl Keyring {
                     item.delete(None).await?;
                 }
             }
+            #[cfg(feature = "mock")]
+            Self::Mock(backend) => backend.delete(attributes)?,
             Self::File(keyring) => {
                 let kg = keyring.read().await;
                 match kg.as_ref() {
@@ -167,6 +148,8 @@ impl Keyring {
                 let items = backend.items().await?;
                 items.into_iter().map(Item::for_dbus).collect::<Vec<_>>()
             }
+            #[cfg(feature = "mock")]
+            Self::Mock(backend) => backend.items()?.into_iter().map(Item::Mock).collect(),
             Self::File(keyring) => {
                 let kg = keyring.read().await;
                 match kg.as_ref() {
@@ -201,6 +184,8 @@ impl Keyring {
                     .create_item(label, attributes, secret, replace, None)
                     .await?;
             }
+            #[cfg(feature = "mock")]
+            Self::Mock(backend) => backend.create_item(label, attributes, secret, replace)?,
             Self::File(keyring) => {
                 let kg = keyring.read().await;
                 match kg.as_ref() {
@@ -227,6 +212,12 @@ impl Keyring {
                 let items = backend.search_items(attributes).await?;
                 items.into_iter().map(Item::for_dbus).collect::<Vec<_>>()
             }
+            #[cfg(feature = "mock")]
+            Self::Mock(backend) => backend
+                .search_items(attributes)?
+                .into_iter()
+                .map(Item::Mock)
+                .collect(),
             Self::File(keyring) => {
                 let kg = keyring.read().await;
                 match kg.as_ref() {
@@ -249,6 +240,300 @@ impl Keyring {
         };
         Ok(items)
     }
+
+    /// Receive the changes made to the keyring.
+    ///
+    /// With the DBus backend, the changes made by the application itself are
+    /// received too. With the file backend, only the writes of other processes
+    /// are received, and the keyring is reloaded before they are.
+    pub async fn receive_changed(&self) -> Result<impl Stream<Item = Event> + '_> {
+        let events = match self {
+            Self::DBus(backend) => {
+                let created = backend
+                    .receive_item_created()
+                    .await?
+                    .map(|_| Event::ItemCreated);
+                let changed = backend
+                    .receive_item_changed()
+                    .await?
+                    .map(|_| Event::ItemChanged);
+                let deleted = backend
+                    .receive_item_deleted()
+                    .await?
+                    .map(|_| Event::ItemDeleted);
+                stream::select(created, stream::select(changed, deleted)).boxed()
+            }
+            #[cfg(feature = "mock")]
+            Self::Mock(_) => stream::pending().boxed(),
+            Self::File(keyring) => {
+                let path = keyring
+                    .read()
+                    .await
+                    .as_ref()
+                    .expect("Keyring must exist")
+                    .path()
+                    .map(ToOwned::to_owned);
+                let Some(path) = path else {
+                    // Nobody else can write a temporary keyring
+                    return Ok(stream::pending().boxed());
+                };
+                file::watch::watch(&path)?
+                    .filter_map(move |()| async move {
+                        let kg = keyring.read().await;
+                        match kg.as_ref().expect("Keyring must exist").reload().await {
+                            Ok(is_reloaded) => is_reloaded.then_some(Event::Reloaded),
+                            Err(_err) => {
+                                #[cfg(feature = "tracing")]
+                                tracing::error!("Failed to reload the keyring file: {_err}");
+                                None
+                            }
+                        }
+                    })
+                    .boxed()
+            }
+        };
+        Ok(events)
+    }
+}
+
+/// A change made to a [`Keyring`], see [`Keyring::receive_changed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// An item was created.
+    ItemCreated,
+    /// An item was modified.
+    ItemChanged,
+    /// An item was deleted.
+    ItemDeleted,
+    /// The keyring file was written by another process, and reloaded.
+    Reloaded,
+}
+
+#[cfg(feature = "mock")]
+impl From<crate::mock::Keyring> for Keyring {
+    fn from(keyring: crate::mock::Keyring) -> Self {
+        Self::Mock(keyring)
+    }
+}
+
+/// A backend that can be used by a [`Keyring`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// An encrypted file, whose secret is retrieved from the
+    /// [`org.freedesktop.portal.Secret`](https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Secret.html)
+    /// portal by default.
+    File,
+    /// The [Secret Service](crate::dbus).
+    DBus,
+}
+
+impl FromStr for Backend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "file" => Ok(Self::File),
+            "dbus" => Ok(Self::DBus),
+            _ => Err(Error::InvalidBackend(s.to_owned())),
+        }
+    }
+}
+
+/// Configure the backend used by a [`Keyring`].
+///
+/// By default, the file backend is used if the application is sandboxed,
+/// falling back to the Secret Service if the portal is not available. The
+/// Secret Service is used otherwise. This can be overridden with
+/// [`KeyringBuilder::backend`] or [`KeyringBuilder::fallback_order`], or with
+/// the [`KeyringBuilder::BACKEND_ENV`] environment variable.
+///
+/// ```no_run
+/// # async fn run() -> oo7::Result<()> {
+/// let keyring = oo7::Keyring::builder()
+///     .backend(oo7::Backend::File)
+///     .path("/tmp/test.keyring")
+///     .secret(b"a secret".to_vec())
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct KeyringBuilder {
+    backends: Option<Vec<Backend>>,
+    alias: Option<String>,
+    path: Option<PathBuf>,
+    secret: Option<Secret>,
+}
+
+impl KeyringBuilder {
+    /// The environment variable overriding the automatic backend selection.
+    ///
+    /// It contains a backend, `file` or `dbus`, or a comma-separated list of
+    /// backends to try in order.
+    pub const BACKEND_ENV: &'static str = "OO7_BACKEND";
+
+    /// Create a new builder, with the automatic backend selection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given backend only.
+    pub fn backend(self, backend: Backend) -> Self {
+        self.fallback_order([backend])
+    }
+
+    /// Try the given backends in order, using the next one if a backend is not
+    /// available.
+    ///
+    /// [`Self::build`] fails with [`Error::NoBackend`] if `backends` is empty.
+    pub fn fallback_order(mut self, backends: impl IntoIterator<Item = Backend>) -> Self {
+        self.backends = Some(backends.into_iter().collect());
+        self
+    }
+
+    /// Use the collection with the given alias instead of the default one.
+    ///
+    /// With the file backend, the alias is used as the keyring name, and the
+    /// [session](dbus::Service::SESSION_COLLECTION) keyring is never stored on
+    /// disk.
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_owned());
+        self
+    }
+
+    /// Use the session collection, which is not persisted.
+    pub fn session(self) -> Self {
+        self.alias(dbus::Service::SESSION_COLLECTION)
+    }
+
+    /// Use the keyring file at `path`, with the file backend.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Use `secret` instead of retrieving it from the portal, with the file
+    /// backend.
+    pub fn secret(mut self, secret: impl Into<Secret>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    fn backends(&self) -> Result<Vec<Backend>> {
+        if let Some(backends) = &self.backends {
+            return Ok(backends.clone());
+        }
+        // Like an unset variable when empty
+        if let Some(value) = std::env::var_os(Self::BACKEND_ENV).filter(|v| !v.is_empty()) {
+            let value = value.to_string_lossy();
+            let backends = value
+                .split(',')
+                .map(Backend::from_str)
+                .collect::<Result<Vec<_>>>()?;
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!("Using the backends {backends:?} from {}", Self::BACKEND_ENV);
+            return Ok(backends);
+        }
+
+        if ashpd::is_sandboxed() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("Application is sandboxed, using the file backend");
+            Ok(vec![Backend::File, Backend::DBus])
+        } else {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                "Application is not sandboxed, falling back to the Secret Service backend"
+            );
+            Ok(vec![Backend::DBus])
+        }
+    }
+
+    /// Create the [`Keyring`], using the first available backend.
+    pub async fn build(self) -> Result<Keyring> {
+        let mut backends = self.backends()?.into_iter().peekable();
+        while let Some(backend) = backends.next() {
+            let result = match backend {
+                Backend::File => self.build_file().await,
+                Backend::DBus => self.build_dbus().await,
+            };
+            match result {
+                Err(err) if backends.peek().is_some() && is_unavailable(&err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("The {backend:?} backend is not available, {err}");
+                }
+                result => return result,
+            }
+        }
+        Err(Error::NoBackend)
+    }
+
+    async fn build_file(&self) -> Result<Keyring> {
+        let secret = match &self.secret {
+            Some(secret) => secret.clone(),
+            None => Secret::from(
+                ashpd::desktop::secret::retrieve()
+                    .await
+                    .map_err(file::Error::from)?,
+            ),
+        };
+        let keyring = match (&self.path, self.alias.as_deref()) {
+            (Some(path), _) => file::UnlockedKeyring::load(path, secret).await?,
+            (None, None | Some(dbus::Service::DEFAULT_COLLECTION)) => {
+                file::UnlockedKeyring::load(file::api::Keyring::default_path()?, secret).await?
+            }
+            (None, Some(dbus::Service::SESSION_COLLECTION)) => {
+                file::UnlockedKeyring::temporary(secret).await?
+            }
+            (None, Some(alias)) => file::UnlockedKeyring::open(alias, secret).await?,
+        };
+        Ok(Keyring::File(Arc::new(RwLock::new(Some(
+            file::Keyring::Unlocked(keyring),
+        )))))
+    }
+
+    async fn build_dbus(&self) -> Result<Keyring> {
+        let service = dbus::Service::new().await?;
+        let collection = match self.alias.as_deref() {
+            None | Some(dbus::Service::DEFAULT_COLLECTION) => service.default_collection().await?,
+            Some(dbus::Service::SESSION_COLLECTION) => service.session_collection().await?,
+            Some(alias) => service
+                .with_alias(alias)
+                .await?
+                .ok_or_else(|| dbus::Error::NotFound(alias.to_owned()))?,
+        };
+        Ok(Keyring::DBus(collection))
+    }
+}
+
+/// Whether the backend can't be used at all, and the next one should be tried.
+fn is_unavailable(err: &Error) -> bool {
+    match err {
+        Error::File(file::Error::Portal(ashpd::Error::PortalNotFound(_))) => true,
+        Error::File(file::Error::Portal(ashpd::Error::Portal(ashpd::PortalError::ZBus(err)))) => {
+            is_missing_service(err)
+        }
+        Error::DBus(dbus::Error::ZBus(err)) => is_missing_service(err),
+        _ => false,
+    }
+}
+
+/// Whether the bus or the service is not available.
+fn is_missing_service(err: &zbus::Error) -> bool {
+    match err {
+        zbus::Error::InputOutput(_) | zbus::Error::Address(_) => true,
+        zbus::Error::FDO(err) => matches!(
+            **err,
+            zbus::fdo::Error::ServiceUnknown(_) | zbus::fdo::Error::NameHasNoOwner(_)
+        ),
+        zbus::Error::MethodError(name, _, _) => matches!(
+            name.as_str(),
+            "org.freedesktop.DBus.Error.ServiceUnknown"
+                | "org.freedesktop.DBus.Error.NameHasNoOwner"
+        ),
+        _ => false,
+    }
 }
 
 /// A generic secret with a label and attributes.
@@ -261,6 +546,9 @@ pub enum Item {
     ),
     #[doc(hidden)]
     DBus(dbus::Item),
+    #[cfg(feature = "mock")]
+    #[doc(hidden)]
+    Mock(crate::mock::Item),
 }
 
 impl Item {
@@ -284,6 +572,8 @@ impl Item {
                 }
             }
             Self::DBus(item) => item.label().await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.label()?,
         };
         Ok(label)
     }
@@ -324,6 +614,8 @@ impl Item {
                 }
             }
             Self::DBus(item) => item.set_label(label).await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.set_label(label)?,
         };
         Ok(())
     }
@@ -344,6 +636,8 @@ impl Item {
                 }
             }
             Self::DBus(item) => item.attributes().await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.attributes()?,
         };
         Ok(attributes)
     }
@@ -397,6 +691,8 @@ impl Item {
                 }
             }
             Self::DBus(item) => item.set_attributes(attributes).await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.set_attributes(attributes)?,
         };
         Ok(())
     }
@@ -437,6 +733,8 @@ impl Item {
                 }
             }
             Self::DBus(item) => item.set_secret(secret).await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.set_secret(secret)?,
         };
         Ok(())
     }
@@ -453,6 +751,8 @@ impl Item {
                 }
             }
             Self::DBus(item) => item.secret().await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.secret()?,
         };
         Ok(secret)
     }
@@ -461,6 +761,8 @@ impl Item {
     pub async fn is_locked(&self) -> Result<bool> {
         match self {
             Self::DBus(item) => item.is_locked().await.map_err(From::from),
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.is_locked(),
             Self::File(item, _) => {
                 let item_guard = item.read().await;
                 let file_item = item_guard.as_ref().expect("Item must exist");
@@ -473,6 +775,8 @@ impl Item {
     pub async fn lock(&self) -> Result<()> {
         match self {
             Self::DBus(item) => item.lock(None).await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.lock()?,
             Self::File(item, keyring) => {
                 let mut item_guard = item.write().await;
                 let item_value = item_guard.take();
@@ -504,6 +808,8 @@ impl Item {
     pub async fn unlock(&self) -> Result<()> {
         match self {
             Self::DBus(item) => item.unlock(None).await?,
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.unlock()?,
             Self::File(item, keyring) => {
                 let mut item_guard = item.write().await;
                 let item_value = item_guard.take();
@@ -562,6 +868,8 @@ impl Item {
             Self::DBus(item) => {
                 item.delete(None).await?;
             }
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.delete()?,
         };
         Ok(())
     }
@@ -570,6 +878,8 @@ impl Item {
     pub async fn created(&self) -> Result<Duration> {
         match self {
             Self::DBus(item) => Ok(item.created().await?),
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.created(),
             Self::File(item, _) => {
                 let item_guard = item.read().await;
                 let file_item = item_guard.as_ref().expect("Item must exist");
@@ -585,6 +895,8 @@ impl Item {
     pub async fn modified(&self) -> Result<Duration> {
         match self {
             Self::DBus(item) => Ok(item.modified().await?),
+            #[cfg(feature = "mock")]
+            Self::Mock(item) => item.modified(),
             Self::File(item, _) => {
                 let item_guard = item.read().await;
                 let file_item = item_guard.as_ref().expect("Item must exist");
@@ -36,6 +36,13 @@ pub mod crypto;
 pub mod dbus;
 pub mod file;
 
+#[cfg(feature = "blocking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+pub mod blocking;
+#[cfg(feature = "mock")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mock")))]
+pub mod mock;
+
 mod keyring;
 mod secret;
 
@@ -44,7 +51,7 @@ pub use ashpd;
 #[cfg_attr(docsrs, doc(cfg(feature = "schema")))]
 pub use error::SchemaError;
 pub use error::{Error, Result};
-pub use keyring::{Item, Keyring};
+pub use keyring::{Backend, Event, Item, Keyring, KeyringBuilder};
 pub use migration::migrate;
 #[cfg(feature = "schema")]
 #[cfg_attr(docsrs, doc(cfg(feature = "schema")))]
@@ -70,7 +77,10 @@ pub trait AsAttributes {
     fn hash(&self, key: &Key) -> Vec<(String, std::result::Result<Mac, crate::crypto::Error>)> {
         self.as_attributes()
             .into_iter()
-            .map(|(k, v)| (k, crypto::compute_mac(v.as_bytes(), key)))
+            .map(|(k, v)| match key.attribute_name(&k) {
+                Ok(name) => (name, crypto::compute_mac(v.as_bytes(), key)),
+                Err(err) => (k, Err(err)),
+            })
             .collect()
     }
 }
@@ -0,0 +1,321 @@
+//! An in-memory backend, to test applications without a Secret Service or a
+//! portal.
+//!
+//! It follows the semantics of the [file backend](crate::file): operations on
+//! a locked keyring or item fail with [`file::Error::Locked`].
+//!
+//! ```
+//! # async fn run() -> oo7::Result<()> {
+//! use oo7::mock::{Failure, Keyring as MockKeyring};
+//!
+//! let mock = MockKeyring::new();
+//! let keyring = oo7::Keyring::from(mock.clone());
+//! keyring
+//!     .create_item("Token", &[("service", "example")], "secret", true)
+//!     .await?;
+//!
+//! // Simulate the user dismissing the unlock prompt
+//! keyring.lock().await?;
+//! mock.fail_next(Failure::Dismissed);
+//! assert!(keyring.unlock().await.is_err());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use crate::{AsAttributes, CONTENT_TYPE_ATTRIBUTE, Result, Secret, dbus, file};
+
+/// A failure to inject in the next operation of a [`Keyring`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The keyring is locked, [`file::Error::Locked`].
+    Locked,
+    /// The prompt was dismissed by the user, [`dbus::Error::Dismissed`].
+    Dismissed,
+    /// The secret doesn't match, [`file::Error::IncorrectSecret`].
+    IncorrectSecret,
+}
+
+impl From<Failure> for crate::Error {
+    fn from(failure: Failure) -> Self {
+        match failure {
+            Failure::Locked => file::Error::Locked.into(),
+            Failure::Dismissed => dbus::Error::Dismissed.into(),
+            Failure::IncorrectSecret => file::Error::IncorrectSecret.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ItemData {
+    id: u64,
+    label: String,
+    attributes: HashMap<String, String>,
+    secret: Secret,
+    is_locked: bool,
+    created: Duration,
+    modified: Duration,
+}
+
+impl ItemData {
+    fn matches(&self, attributes: &HashMap<String, String>) -> bool {
+        attributes
+            .iter()
+            .all(|(key, value)| self.attributes.get(key) == Some(value))
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    items: Vec<ItemData>,
+    is_locked: bool,
+    next_id: u64,
+    failures: VecDeque<Failure>,
+}
+
+impl State {
+    /// Fail with the next injected failure, or if the keyring is locked.
+    fn check(&mut self) -> Result<()> {
+        if let Some(failure) = self.failures.pop_front() {
+            return Err(failure.into());
+        }
+        if self.is_locked {
+            return Err(file::Error::Locked.into());
+        }
+        Ok(())
+    }
+
+    fn item(&mut self, id: u64) -> Result<&mut ItemData> {
+        self.check()?;
+        let item = self
+            .items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or(dbus::Error::Deleted)?;
+        if item.is_locked {
+            return Err(file::Error::Locked.into());
+        }
+        Ok(item)
+    }
+}
+
+fn now() -> Duration {
+    // Same precision as the file backend
+    Duration::from_secs(
+        std::time::SystemTime::UNIX_EPOCH
+            .elapsed()
+            .unwrap()
+            .as_secs(),
+    )
+}
+
+/// An in-memory keyring.
+///
+/// The clones share the same items, so a test can keep one to inject failures
+/// while the code under test uses the [`crate::Keyring`] created from it.
+#[derive(Debug, Clone, Default)]
+pub struct Keyring {
+    state: Arc<Mutex<State>>,
+}
+
+impl Keyring {
+    /// Create an empty and unlocked keyring.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the next operation fail.
+    ///
+    /// The failures are used in the order they are added, one per operation.
+    pub fn fail_next(&self, failure: Failure) {
+        self.state().failures.push_back(failure);
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    fn items_matching(&self, attributes: &HashMap<String, String>) -> Result<Vec<Item>> {
+        let mut state = self.state();
+        state.check()?;
+        Ok(state
+            .items
+            .iter()
+            .filter(|item| item.matches(attributes))
+            .map(|item| Item {
+                id: item.id,
+                keyring: self.clone(),
+            })
+            .collect())
+    }
+
+    pub(crate) fn unlock(&self) -> Result<()> {
+        let mut state = self.state();
+        if let Some(failure) = state.failures.pop_front() {
+            return Err(failure.into());
+        }
+        state.is_locked = false;
+        Ok(())
+    }
+
+    pub(crate) fn lock(&self) -> Result<()> {
+        let mut state = self.state();
+        if let Some(failure) = state.failures.pop_front() {
+            return Err(failure.into());
+        }
+        state.is_locked = true;
+        Ok(())
+    }
+
+    pub(crate) fn is_locked(&self) -> bool {
+        self.state().is_locked
+    }
+
+    pub(crate) fn delete(&self, attributes: &impl AsAttributes) -> Result<()> {
+        let attributes = attributes.as_attributes();
+        let mut state = self.state();
+        state.check()?;
+        state.items.retain(|item| !item.matches(&attributes));
+        Ok(())
+    }
+
+    pub(crate) fn items(&self) -> Result<Vec<Item>> {
+        self.items_matching(&HashMap::new())
+    }
+
+    pub(crate) fn create_item(
+        &self,
+        label: &str,
+        attributes: &impl AsAttributes,
+        secret: impl Into<Secret>,
+        replace: bool,
+    ) -> Result<()> {
+        let secret = secret.into();
+        let mut attributes = attributes.as_attributes();
+        let mut state = self.state();
+        state.check()?;
+        if replace {
+            state.items.retain(|item| !item.matches(&attributes));
+        }
+        attributes
+            .entry(CONTENT_TYPE_ATTRIBUTE.to_owned())
+            .or_insert_with(|| secret.content_type().as_str().to_owned());
+
+        let now = now();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.items.push(ItemData {
+            id,
+            label: label.to_owned(),
+            attributes,
+            secret,
+            is_locked: false,
+            created: now,
+            modified: now,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn search_items(&self, attributes: &impl AsAttributes) -> Result<Vec<Item>> {
+        self.items_matching(&attributes.as_attributes())
+    }
+}
+
+/// An item of an in-memory [`Keyring`].
+#[derive(Debug)]
+pub struct Item {
+    id: u64,
+    keyring: Keyring,
+}
+
+impl Item {
+    fn read<T>(&self, f: impl FnOnce(&ItemData) -> T) -> Result<T> {
+        let mut state = self.keyring.state();
+        state.item(self.id).map(|item| f(item))
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ItemData)) -> Result<()> {
+        let mut state = self.keyring.state();
+        let item = state.item(self.id)?;
+        f(item);
+        item.modified = now();
+        Ok(())
+    }
+
+    /// Lock or unlock the item, which only requires the keyring to be unlocked.
+    fn set_locked(&self, is_locked: bool) -> Result<()> {
+        let mut state = self.keyring.state();
+        state.check()?;
+        let item = state
+            .items
+            .iter_mut()
+            .find(|item| item.id == self.id)
+            .ok_or(dbus::Error::Deleted)?;
+        item.is_locked = is_locked;
+        Ok(())
+    }
+
+    pub(crate) fn label(&self) -> Result<String> {
+        self.read(|item| item.label.clone())
+    }
+
+    pub(crate) fn set_label(&self, label: &str) -> Result<()> {
+        self.update(|item| item.label = label.to_owned())
+    }
+
+    pub(crate) fn attributes(&self) -> Result<HashMap<String, String>> {
+        self.read(|item| item.attributes.clone())
+    }
+
+    pub(crate) fn set_attributes(&self, attributes: &impl AsAttributes) -> Result<()> {
+        let attributes = attributes.as_attributes();
+        self.update(|item| item.attributes = attributes)
+    }
+
+    pub(crate) fn set_secret(&self, secret: impl Into<Secret>) -> Result<()> {
+        let secret = secret.into();
+        self.update(|item| item.secret = secret)
+    }
+
+    pub(crate) fn secret(&self) -> Result<Secret> {
+        self.read(|item| item.secret.clone())
+    }
+
+    pub(crate) fn is_locked(&self) -> Result<bool> {
+        let state = self.keyring.state();
+        let item = state
+            .items
+            .iter()
+            .find(|item| item.id == self.id)
+            .ok_or(dbus::Error::Deleted)?;
+        Ok(state.is_locked || item.is_locked)
+    }
+
+    pub(crate) fn lock(&self) -> Result<()> {
+        self.set_locked(true)
+    }
+
+    pub(crate) fn unlock(&self) -> Result<()> {
+        self.set_locked(false)
+    }
+
+    pub(crate) fn delete(&self) -> Result<()> {
+        let mut state = self.keyring.state();
+        state.item(self.id)?;
+        state.items.retain(|item| item.id != self.id);
+        Ok(())
+    }
+
+    pub(crate) fn created(&self) -> Result<Duration> {
+        self.read(|item| item.created)
+    }
+
+    pub(crate) fn modified(&self) -> Result<Duration> {
+        self.read(|item| item.modified)
+    }
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "blocking")]
+
+use oo7::{Backend, Keyring, Secret, blocking};
+use tempfile::tempdir;
+
+fn strong_key() -> Secret {
+    Secret::from([1, 2].into_iter().cycle().take(64).collect::<Vec<_>>())
+}
+
+#[test]
+fn file_keyring() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().join("blocking.keyring");
+
+    let keyring = blocking::file::UnlockedKeyring::load(&path, strong_key()).unwrap();
+    keyring
+        .create_item("Item", &[("app", "blocking")], "secret", false)
+        .unwrap();
+    assert_eq!(keyring.n_items(), 1);
+
+    let keyring = blocking::file::UnlockedKeyring::load(&path, strong_key()).unwrap();
+    let item = keyring
+        .lookup_item(&[("app", "blocking")])
+        .unwrap()
+        .unwrap();
+    assert_eq!(item.label(), "Item");
+    assert_eq!(item.secret(), Secret::text("secret"));
+
+    keyring.delete(&[("app", "blocking")]).unwrap();
+    assert!(keyring.items().unwrap().is_empty());
+}
+
+#[test]
+fn keyring() {
+    let temp_dir = tempdir().unwrap();
+    let builder = Keyring::builder()
+        .backend(Backend::File)
+        .path(temp_dir.path().join("blocking.keyring"))
+        .secret(strong_key());
+
+    let keyring = blocking::Keyring::with_builder(builder).unwrap();
+    keyring
+        .create_item("Item", &[("app", "blocking")], "secret", true)
+        .unwrap();
+    let items = keyring.search_items(&[("app", "blocking")]).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].secret().unwrap(), Secret::text("secret"));
+
+    items[0].set_label("New label").unwrap();
+    assert_eq!(items[0].label().unwrap(), "New label");
+}
+
+#[tokio::test]
+#[cfg(feature = "tokio")]
+async fn inside_runtime() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().join("blocking.keyring");
+
+    let keyring = blocking::file::UnlockedKeyring::load(&path, strong_key()).unwrap();
+    keyring
+        .create_item("Item", &[("app", "blocking")], "secret", false)
+        .unwrap();
+    assert_eq!(keyring.items().unwrap().len(), 1);
+}
+
+#[test]
+#[cfg(feature = "async-std")]
+fn inside_executor() {
+    async_io::block_on(async {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("blocking.keyring");
+
+        let keyring = blocking::file::UnlockedKeyring::load(&path, strong_key()).unwrap();
+        keyring
+            .create_item("Item", &[("app", "blocking")], "secret", false)
+            .unwrap();
+        assert_eq!(keyring.items().unwrap().len(), 1);
+    });
+}
@@ -46,3 +46,18 @@ async fn plain_session() {
     let service = Service::plain().await.unwrap();
     assert!(service.default_collection().await.is_ok());
 }
+
+#[tokio::test]
+#[cfg(feature = "tokio")]
+async fn set_alias() {
+    let service = Service::new().await.unwrap();
+    let collection = service.default_collection().await.unwrap();
+
+    service
+        .set_alias("oo7-test-alias", &collection)
+        .await
+        .unwrap();
+
+    let found_collection = service.with_alias("oo7-test-alias").await.unwrap();
+    assert_eq!(found_collection.unwrap().path(), collection.path());
+}
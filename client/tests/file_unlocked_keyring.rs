@@ -317,8 +317,14 @@ async fn delete_broken_items() -> Result<(), Error> {
     }
     drop(keyring);
 
-    // 3) Load with the correct password and run the deletion.
-    let keyring = UnlockedKeyring::load(&keyring_path, Secret::blob("test")).await?;
+    // 3) Load with the correct password and run the deletion. The file mixing
+    //    secrets has no integrity MAC, it has to be loaded unchecked.
+    assert!(matches!(
+        UnlockedKeyring::load(&keyring_path, Secret::blob("test")).await,
+        Err(Error::IntegrityMismatch)
+    ));
+    let keyring =
+        unsafe { UnlockedKeyring::load_unchecked(&keyring_path, Secret::blob("test")).await? };
     let removed = keyring.delete_broken_items().await?;
     assert!(
         removed >= BROKEN_TO_ADD,
@@ -329,11 +335,76 @@ async fn delete_broken_items() -> Result<(), Error> {
 
     // Second call should find nothing left to clean up.
     assert_eq!(keyring.delete_broken_items().await?, 0);
+    // Authenticated again once repaired
+    UnlockedKeyring::load(&keyring_path, Secret::blob("test")).await?;
 
     fs::remove_file(keyring_path).await?;
     Ok(())
 }
 
+#[tokio::test]
+async fn dump_broken_items() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("test.keyring");
+    let broken_path = temp_dir.path().join("broken.keyring");
+
+    let keyring = UnlockedKeyring::load(&path, strong_key()).await?;
+    keyring
+        .create_item("valid", &[("attr_valid", "value")], "password", false)
+        .await?;
+    drop(keyring);
+
+    let wrong_secret = Secret::text("wrong_password");
+    let keyring = unsafe { UnlockedKeyring::load_unchecked(&path, wrong_secret.clone()).await? };
+    for i in 0..2 {
+        keyring
+            .create_item(
+                &format!("bad{i}"),
+                &[("attr_bad", i.to_string())],
+                "pw_bad",
+                false,
+            )
+            .await?;
+    }
+    drop(keyring);
+
+    let keyring = unsafe { UnlockedKeyring::load_unchecked(&path, strong_key()).await? };
+    let invalid_items = keyring
+        .all_items()
+        .await?
+        .into_iter()
+        .filter_map(Result::err)
+        .collect::<Vec<_>>();
+    assert_eq!(invalid_items.len(), 2);
+    assert!(matches!(invalid_items[0].error(), Error::MacError));
+    assert!(
+        invalid_items[0]
+            .attribute_names()
+            .contains(&"attr_bad".to_owned())
+    );
+
+    assert_eq!(keyring.dump_broken_items(&broken_path).await?, 2);
+    // Nothing is deleted from the original keyring
+    assert_eq!(keyring.all_items().await?.len(), 3);
+
+    // The broken items can be recovered with the secret used to create them,
+    // the file has no integrity MAC until it is written with that secret
+    let broken_keyring =
+        unsafe { UnlockedKeyring::load_unchecked(&broken_path, wrong_secret.clone()).await? };
+    let mut labels = broken_keyring
+        .items()
+        .await?
+        .iter()
+        .map(|item| item.label().to_owned())
+        .collect::<Vec<_>>();
+    labels.sort();
+    assert_eq!(labels, ["bad0", "bad1"]);
+    broken_keyring.write().await?;
+    UnlockedKeyring::load(&broken_path, wrong_secret).await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn change_secret() -> Result<(), Error> {
     let data_dir = tempdir()?;
@@ -843,6 +914,40 @@ async fn bulk_create_items() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn insert_items() -> Result<(), Error> {
+    let temp_dir = tempdir().unwrap();
+    let keyring_path = temp_dir.path().join("insert_items_test.keyring");
+    let keyring = UnlockedKeyring::load(&keyring_path, strong_key()).await?;
+    keyring
+        .create_item("Old", &[("app", "insert-app")], "old", false)
+        .await?;
+
+    let created = std::time::Duration::from_secs(1_600_000_000);
+    let modified = std::time::Duration::from_secs(1_700_000_000);
+    let items = vec![
+        UnlockedItem::new("Restored 1", &[("app", "insert-app")], "secret1")
+            .with_timestamps(created, modified),
+        UnlockedItem::new("Restored 2", &[("app", "other-app")], "secret2")
+            .with_timestamps(created, created),
+    ];
+    keyring.insert_items(items, true).await?;
+
+    let keyring = UnlockedKeyring::load(&keyring_path, strong_key()).await?;
+    assert_eq!(keyring.n_items().await, 2);
+    let item = keyring
+        .lookup_item(&[("app", "insert-app")])
+        .await?
+        .unwrap();
+    assert_eq!(item.label(), "Restored 1");
+    assert_eq!(item.secret(), Secret::text("secret1"));
+    assert_eq!(item.created(), created);
+    assert_eq!(item.modified(), modified);
+    let item = keyring.lookup_item(&[("app", "other-app")]).await?.unwrap();
+    assert_eq!(item.modified(), created);
+    Ok(())
+}
+
 #[tokio::test]
 async fn partially_corrupted_keyring_error() -> Result<(), Error> {
     let temp_dir = tempdir().unwrap();
@@ -990,3 +1095,475 @@ async fn set_attributes() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn receive_changed() -> Result<(), Error> {
+    use futures_util::StreamExt;
+
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("shared.keyring");
+
+    let keyring = UnlockedKeyring::load(&path, strong_key()).await?;
+    let other = UnlockedKeyring::load(&path, strong_key()).await?;
+
+    let changed = keyring.receive_changed()?;
+    futures_util::pin_mut!(changed);
+
+    other
+        .create_item("Other item", &[("key", "other")], "secret", false)
+        .await?;
+    tokio::time::timeout(std::time::Duration::from_secs(5), changed.next())
+        .await
+        .expect("The change was not received")
+        .unwrap();
+
+    let items = keyring.items().await?;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].label(), "Other item");
+
+    // The keyring was reloaded, so writing doesn't fail anymore
+    keyring
+        .create_item("Item", &[("key", "value")], "secret", false)
+        .await?;
+    assert!(!keyring.reload().await?);
+
+    assert!(other.reload().await?);
+    assert_eq!(other.items().await?.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn merge_on_conflict() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("merged.keyring");
+
+    let keyring = UnlockedKeyring::load(&path, strong_key())
+        .await?
+        .with_merge_on_conflict(true);
+    let other = UnlockedKeyring::load(&path, strong_key())
+        .await?
+        .with_merge_on_conflict(true);
+
+    keyring
+        .create_item("A", &[("key", "a")], "secret", false)
+        .await?;
+    other
+        .create_item("B", &[("key", "b")], "secret", false)
+        .await?;
+    assert_eq!(other.n_items().await, 2);
+
+    // The deletion of A and the creation of C are both kept
+    other.delete(&[("key", "a")]).await?;
+    keyring
+        .create_item("C", &[("key", "c")], "secret", false)
+        .await?;
+    let mut labels = keyring
+        .items()
+        .await?
+        .into_iter()
+        .map(|item| item.label().to_owned())
+        .collect::<Vec<_>>();
+    labels.sort();
+    assert_eq!(labels, ["B", "C"]);
+
+    // The last modification wins
+    other.reload().await?;
+    keyring
+        .create_item("B", &[("key", "b")], "first", true)
+        .await?;
+    other
+        .create_item("B", &[("key", "b")], "second", true)
+        .await?;
+    let reloaded = UnlockedKeyring::load(&path, strong_key()).await?;
+    let items = reloaded.search_items(&[("key", "b")]).await?;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].secret(), Secret::text("second"));
+    assert_eq!(reloaded.n_items().await, 2);
+
+    // A removed file has no changes to merge, all the items are written
+    fs::remove_file(&path).await?;
+    keyring
+        .create_item("D", &[("key", "d")], "secret", false)
+        .await?;
+    let reloaded = UnlockedKeyring::load(&path, strong_key()).await?;
+    assert_eq!(reloaded.n_items().await, 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn merge_on_conflict_upgrade_v1() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("default.keyring");
+    let fixture_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/default.keyring");
+    fs::copy(&fixture_path, &path).await?;
+
+    let keyring = UnlockedKeyring::load(&path, Secret::from("test"))
+        .await?
+        .with_merge_on_conflict(true);
+    let other = UnlockedKeyring::load(&path, Secret::from("test"))
+        .await?
+        .with_merge_on_conflict(true);
+    let items = keyring.items().await?;
+    let deleted = &items[0];
+
+    // Both sides upgrade the file to the v2 format, with different salts
+    other
+        .create_item("C", &[("key", "c")], "secret", false)
+        .await?;
+    keyring
+        .delete(&[("xdg:schema", "org.gnome.keyring.Note")])
+        .await?;
+
+    let reloaded = UnlockedKeyring::load(&path, Secret::from("test")).await?;
+    let merged = reloaded.items().await?;
+    assert_eq!(merged.len(), items.len());
+    assert!(!merged.contains(deleted));
+    assert_eq!(reloaded.search_items(&[("key", "c")]).await?.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn target_file_changed() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("conflict.keyring");
+
+    let keyring = UnlockedKeyring::load(&path, strong_key()).await?;
+    let other = UnlockedKeyring::load(&path, strong_key()).await?;
+
+    keyring
+        .create_item("A", &[("key", "a")], "secret", false)
+        .await?;
+    let result = other
+        .create_item("B", &[("key", "b")], "secret", false)
+        .await;
+    assert!(matches!(result, Err(Error::TargetFileChanged(_))));
+    // The temporary file is removed
+    assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn upgrade_v1_on_write() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("default.keyring");
+    let fixture_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/default.keyring");
+    fs::copy(&fixture_path, &path).await?;
+
+    let keyring = UnlockedKeyring::load(&path, Secret::from("test")).await?;
+    assert!(matches!(
+        LockedKeyring::load(&path).await?.kdf().await,
+        Kdf::Pbkdf2 { .. }
+    ));
+    let items = keyring.items().await?;
+    assert!(!items.is_empty());
+
+    keyring.write().await?;
+
+    let locked = LockedKeyring::load(&path).await?;
+    assert_eq!(locked.version().await, (2, 0));
+    assert!(matches!(locked.kdf().await, Kdf::Argon2id { .. }));
+    let keyring = locked.unlock(Secret::from("test")).await?;
+    let upgraded_items = keyring.items().await?;
+    assert_eq!(upgraded_items.len(), items.len());
+    for item in &items {
+        assert!(upgraded_items.contains(item));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rekey() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("rekey.keyring");
+
+    let keyring = UnlockedKeyring::load(&path, strong_key()).await?;
+    keyring
+        .create_item("Label", &[("key", "value")], "secret", false)
+        .await?;
+    let old_key = keyring.key().await.unwrap();
+
+    let kdf = Kdf::Argon2id {
+        memory_cost: 32 * 1024,
+        time_cost: 3,
+        parallelism: 1,
+    };
+    keyring.rekey(kdf).await?;
+    assert_eq!(keyring.kdf().await, kdf);
+    let new_key = keyring.key().await.unwrap();
+    assert_ne!(
+        AsRef::<[u8]>::as_ref(&*new_key),
+        AsRef::<[u8]>::as_ref(&*old_key)
+    );
+
+    // The file was written with the new parameters
+    let locked = LockedKeyring::load(&path).await?;
+    assert_eq!(locked.kdf().await, kdf);
+    let keyring = locked.unlock(strong_key()).await?;
+    let items = keyring.search_items(&[("key", "value")]).await?;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].secret(), Secret::text("secret"));
+
+    // Weak parameters are refused, and the keyring is kept as is
+    let weak_kdf = Kdf::Argon2id {
+        memory_cost: 1024,
+        time_cost: 3,
+        parallelism: 1,
+    };
+    assert!(matches!(
+        keyring.rekey(weak_kdf).await,
+        Err(Error::WeakKey(WeakKeyError::MemoryCostTooLow(1024)))
+    ));
+    assert!(matches!(
+        keyring
+            .rekey(Kdf::Pbkdf2 {
+                iteration_count: 200000
+            })
+            .await,
+        Err(Error::WeakKey(WeakKeyError::KdfObsolete))
+    ));
+    assert_eq!(keyring.kdf().await, kdf);
+    assert_eq!(LockedKeyring::load(&path).await?.kdf().await, kdf);
+
+    // Changing the secret keeps the parameters
+    keyring
+        .change_secret(Secret::from("another strong secret"))
+        .await?;
+    assert_eq!(keyring.kdf().await, kdf);
+    assert_eq!(LockedKeyring::load(&path).await?.kdf().await, kdf);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn hidden_metadata() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("hidden.keyring");
+
+    let keyring = UnlockedKeyring::load(&path, strong_key()).await?;
+    keyring
+        .create_item("Label", &[("service", "mail")], "secret", false)
+        .await?;
+    assert!(!keyring.hidden_metadata().await);
+
+    keyring.set_hidden_metadata(true).await?;
+    assert!(keyring.hidden_metadata().await);
+    keyring
+        .create_item("Other", &[("service", "chat")], "other secret", false)
+        .await?;
+
+    let locked = LockedKeyring::load(&path).await?;
+    assert_eq!(locked.version().await, (2, 1));
+    for item in locked.items().await? {
+        assert!(item.attribute_names().all(|name| name != "service"));
+    }
+    let keyring = locked.unlock(strong_key()).await?;
+    assert!(keyring.hidden_metadata().await);
+    let items = keyring.search_items(&[("service", "mail")]).await?;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].secret(), Secret::text("secret"));
+    assert_eq!(items[0].attributes()["service"], "mail");
+
+    // Replacing and deleting items keeps working
+    keyring
+        .create_item("Label", &[("service", "mail")], "new secret", true)
+        .await?;
+    assert_eq!(keyring.n_items().await, 2);
+    keyring.delete(&[("service", "chat")]).await?;
+    assert_eq!(keyring.n_items().await, 1);
+
+    keyring.set_hidden_metadata(false).await?;
+    let locked = LockedKeyring::load(&path).await?;
+    assert_eq!(locked.version().await, (2, 0));
+    let item = locked.items().await?.remove(0);
+    assert!(item.attribute_names().any(|name| name == "service"));
+    let keyring = locked.unlock(strong_key()).await?;
+    let items = keyring.search_items(&[("service", "mail")]).await?;
+    assert_eq!(items[0].secret(), Secret::text("new secret"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn index_updates() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("index.keyring");
+
+    let keyring = UnlockedKeyring::load(&path, strong_key()).await?;
+    let items = (0..10)
+        .map(|i| {
+            (
+                format!("Item {i}"),
+                HashMap::from([
+                    ("parity".to_owned(), (i % 2).to_string()),
+                    ("number".to_owned(), i.to_string()),
+                ]),
+                Secret::text(format!("secret {i}")),
+                false,
+            )
+        })
+        .collect();
+    keyring.create_items(items).await?;
+    assert_eq!(keyring.search_items(&[("parity", "0")]).await?.len(), 5);
+    assert_eq!(
+        keyring.lookup_item_index(&[("number", "3")]).await?,
+        Some(3)
+    );
+
+    // The following items are shifted
+    keyring.delete_item_index(1).await?;
+    assert_eq!(
+        keyring.lookup_item_index(&[("number", "3")]).await?,
+        Some(2)
+    );
+    assert_eq!(keyring.search_items(&[("parity", "1")]).await?.len(), 4);
+
+    let mut item = keyring.lookup_item(&[("number", "3")]).await?.unwrap();
+    item.set_attributes(&[("number", "42")]);
+    keyring.replace_item_index(2, &item).await?;
+    assert!(keyring.lookup_item(&[("number", "3")]).await?.is_none());
+    assert_eq!(
+        keyring.lookup_item_index(&[("number", "42")]).await?,
+        Some(2)
+    );
+
+    keyring.delete(&[("parity", "0")]).await?;
+    assert_eq!(keyring.n_items().await, 4);
+    assert!(keyring.search_items(&[("parity", "0")]).await?.is_empty());
+    let numbers = keyring
+        .search_items(&[("parity", "1")])
+        .await?
+        .into_iter()
+        .map(|item| item.attributes()["number"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(numbers, ["5", "7", "9"]);
+
+    // Written by another process
+    let other = UnlockedKeyring::load(&path, strong_key()).await?;
+    other
+        .create_item("Other", &[("number", "100")], "other", false)
+        .await?;
+    assert!(keyring.reload().await?);
+    assert_eq!(
+        keyring.lookup_item_index(&[("number", "100")]).await?,
+        Some(4)
+    );
+
+    keyring
+        .change_secret(Secret::from("another secret"))
+        .await?;
+    assert_eq!(keyring.search_items(&[("parity", "1")]).await?.len(), 3);
+    assert_eq!(
+        keyring.lookup_item_index(&[("number", "100")]).await?,
+        Some(4)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rollback_counter() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("rollback.keyring");
+    let counter_path = temp_dir.path().join("state/rollback.counter");
+
+    let keyring = LockedKeyring::load(&path)
+        .await?
+        .with_rollback_counter(&counter_path)
+        .unlock(strong_key())
+        .await?;
+    keyring
+        .create_item("Label", &[("key", "value")], "secret", false)
+        .await?;
+    let old_copy = fs::read(&path).await?;
+    keyring.delete(&[("key", "value")]).await?;
+    let mode =
+        std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&counter_path).await?.permissions());
+    assert_eq!(mode & 0o777, 0o600);
+
+    // The old copy is authentic, only the counter tells it apart
+    let old_path = temp_dir.path().join("old.keyring");
+    fs::write(&old_path, &old_copy).await?;
+    LockedKeyring::load(&old_path)
+        .await?
+        .unlock(strong_key())
+        .await?;
+    fs::write(&path, &old_copy).await?;
+    assert!(matches!(
+        LockedKeyring::load(&path)
+            .await?
+            .with_rollback_counter(&counter_path)
+            .unlock(strong_key())
+            .await,
+        Err(Error::RolledBack {
+            usage_count: 1,
+            counter: 2
+        })
+    ));
+
+    // Deleting the file is detected too
+    fs::remove_file(&path).await?;
+    assert!(matches!(
+        LockedKeyring::load(&path)
+            .await?
+            .with_rollback_counter(&counter_path)
+            .unlock(strong_key())
+            .await,
+        Err(Error::RolledBack { .. })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reload_verified() -> Result<(), Error> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("reload.keyring");
+    let counter_path = temp_dir.path().join("state/reload.counter");
+
+    let keyring = UnlockedKeyring::load(&path, strong_key())
+        .await?
+        .with_rollback_counter(&counter_path)
+        .with_merge_on_conflict(true);
+    keyring
+        .create_item("A", &[("key", "a")], "secret", false)
+        .await?;
+    let old_copy = fs::read(&path).await?;
+    keyring
+        .create_item("B", &[("key", "b")], "secret", false)
+        .await?;
+
+    // Rolled back while unlocked, the merge fails too
+    fs::write(&path, &old_copy).await?;
+    assert!(matches!(
+        keyring.reload().await,
+        Err(Error::RolledBack {
+            usage_count: 1,
+            counter: 2
+        })
+    ));
+    assert!(matches!(
+        keyring
+            .create_item("C", &[("key", "c")], "secret", false)
+            .await,
+        Err(Error::RolledBack { .. })
+    ));
+
+    // Replaced by a file without integrity MAC, like a v1 one, whose usage
+    // count cannot be trusted
+    let unchecked =
+        unsafe { UnlockedKeyring::load_unchecked(&path, Secret::text("wrong_password")).await? };
+    unchecked
+        .create_item("C", &[("key", "c")], "secret", false)
+        .await?;
+    assert!(matches!(
+        keyring.reload().await,
+        Err(Error::IntegrityMismatch)
+    ));
+
+    Ok(())
+}
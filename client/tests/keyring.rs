@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 #[cfg(feature = "async-std")]
 use async_lock::RwLock;
-use oo7::{Keyring, Secret, dbus, file};
+use oo7::{Backend, Keyring, Secret, dbus, file};
 use tempfile::tempdir;
 #[cfg(feature = "tokio")]
 use tokio::sync::RwLock;
@@ -621,3 +621,98 @@ async fn file_item_lock_with_locked_keyring_fails() {
     let result = item.lock().await;
     assert!(matches!(result, Err(oo7::Error::File(file::Error::Locked))));
 }
+
+#[tokio::test]
+#[cfg(feature = "tokio")]
+async fn builder_file_backend() {
+    let temp_dir = tempdir().unwrap();
+    let keyring_path = temp_dir.path().join("builder.keyring");
+    let secret = Secret::from([1, 2].into_iter().cycle().take(64).collect::<Vec<_>>());
+
+    let keyring = Keyring::builder()
+        .backend(Backend::File)
+        .path(&keyring_path)
+        .secret(secret.clone())
+        .build()
+        .await
+        .unwrap();
+    assert!(matches!(keyring, Keyring::File(_)));
+    keyring
+        .create_item("Test", &[("app", "builder")], "secret", false)
+        .await
+        .unwrap();
+    assert!(keyring_path.exists());
+
+    let keyring = Keyring::builder()
+        .backend(Backend::File)
+        .path(&keyring_path)
+        .secret(secret.clone())
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(keyring.items().await.unwrap().len(), 1);
+
+    // The session keyring is never written to disk
+    let keyring = Keyring::builder()
+        .fallback_order([Backend::File, Backend::DBus])
+        .session()
+        .secret(secret)
+        .build()
+        .await
+        .unwrap();
+    assert!(matches!(keyring, Keyring::File(_)));
+    assert!(keyring.items().await.unwrap().is_empty());
+
+    assert!(matches!(
+        Keyring::builder().fallback_order([]).build().await,
+        Err(oo7::Error::NoBackend)
+    ));
+}
+
+#[test]
+fn backend_from_str() {
+    assert_eq!("file".parse::<Backend>().unwrap(), Backend::File);
+    assert_eq!(" DBus".parse::<Backend>().unwrap(), Backend::DBus);
+    assert!(matches!(
+        "kwallet".parse::<Backend>(),
+        Err(oo7::Error::InvalidBackend(_))
+    ));
+}
+
+#[tokio::test]
+#[cfg(feature = "tokio")]
+async fn receive_changed() {
+    use futures_util::StreamExt;
+    use oo7::Event;
+
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().join("shared.keyring");
+    let secret = Secret::from([1, 2].into_iter().cycle().take(64).collect::<Vec<_>>());
+
+    let keyring = Keyring::builder()
+        .backend(Backend::File)
+        .path(&path)
+        .secret(secret.clone())
+        .build()
+        .await
+        .unwrap();
+    let other = file::UnlockedKeyring::load(&path, secret).await.unwrap();
+
+    let changed = keyring.receive_changed().await.unwrap();
+    futures_util::pin_mut!(changed);
+
+    other
+        .create_item("Item", &[("test-name", "receive_changed")], "secret", false)
+        .await
+        .unwrap();
+    let event = tokio::time::timeout(std::time::Duration::from_secs(5), changed.next())
+        .await
+        .expect("The change was not received");
+    assert_eq!(event, Some(Event::Reloaded));
+
+    let items = keyring
+        .search_items(&[("test-name", "receive_changed")])
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+}
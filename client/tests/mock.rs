@@ -0,0 +1,118 @@
+#![cfg(feature = "mock")]
+
+use oo7::{
+    Keyring, dbus, file,
+    mock::{self, Failure},
+};
+
+fn keyring() -> (mock::Keyring, Keyring) {
+    let mock = mock::Keyring::new();
+    let keyring = Keyring::from(mock.clone());
+    (mock, keyring)
+}
+
+#[tokio::test]
+async fn create_and_search() {
+    let (_, keyring) = keyring();
+
+    keyring
+        .create_item("Alice", &[("app", "mock"), ("user", "alice")], "a", false)
+        .await
+        .unwrap();
+    keyring
+        .create_item("Bob", &[("app", "mock"), ("user", "bob")], "b", false)
+        .await
+        .unwrap();
+    assert_eq!(keyring.items().await.unwrap().len(), 2);
+
+    let items = keyring.search_items(&[("user", "alice")]).await.unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].label().await.unwrap(), "Alice");
+    assert_eq!(items[0].secret().await.unwrap(), oo7::Secret::text("a"));
+    assert_eq!(
+        items[0].attributes().await.unwrap()[oo7::CONTENT_TYPE_ATTRIBUTE],
+        "text/plain"
+    );
+    assert_eq!(
+        items[0].created().await.unwrap(),
+        items[0].modified().await.unwrap()
+    );
+
+    // Replacing removes the items matching the attributes
+    keyring
+        .create_item("Alice 2", &[("app", "mock"), ("user", "alice")], "c", true)
+        .await
+        .unwrap();
+    let items = keyring.search_items(&[("app", "mock")]).await.unwrap();
+    assert_eq!(items.len(), 2);
+    let items = keyring.search_items(&[("user", "alice")]).await.unwrap();
+    assert_eq!(items[0].label().await.unwrap(), "Alice 2");
+
+    items[0].delete().await.unwrap();
+    assert!(matches!(
+        items[0].secret().await,
+        Err(oo7::Error::DBus(dbus::Error::Deleted))
+    ));
+    keyring.delete(&[("app", "mock")]).await.unwrap();
+    assert!(keyring.items().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn lock_unlock() {
+    let (_, keyring) = keyring();
+    keyring
+        .create_item("Item", &[("app", "mock")], "secret", false)
+        .await
+        .unwrap();
+    let items = keyring.items().await.unwrap();
+
+    keyring.lock().await.unwrap();
+    assert!(keyring.is_locked().await.unwrap());
+    assert!(items[0].is_locked().await.unwrap());
+    assert!(matches!(
+        keyring.items().await,
+        Err(oo7::Error::File(file::Error::Locked))
+    ));
+    assert!(matches!(
+        items[0].secret().await,
+        Err(oo7::Error::File(file::Error::Locked))
+    ));
+
+    keyring.unlock().await.unwrap();
+    items[0].lock().await.unwrap();
+    assert!(matches!(
+        items[0].set_secret("new").await,
+        Err(oo7::Error::File(file::Error::Locked))
+    ));
+    items[0].unlock().await.unwrap();
+    items[0].set_secret("new").await.unwrap();
+    assert_eq!(items[0].secret().await.unwrap(), oo7::Secret::text("new"));
+}
+
+#[tokio::test]
+async fn failures() {
+    let (mock, keyring) = keyring();
+
+    keyring.lock().await.unwrap();
+    mock.fail_next(Failure::Dismissed);
+    mock.fail_next(Failure::IncorrectSecret);
+    assert!(matches!(
+        keyring.unlock().await,
+        Err(oo7::Error::DBus(dbus::Error::Dismissed))
+    ));
+    assert!(matches!(
+        keyring.unlock().await,
+        Err(oo7::Error::File(file::Error::IncorrectSecret))
+    ));
+    keyring.unlock().await.unwrap();
+    assert!(!keyring.is_locked().await.unwrap());
+
+    mock.fail_next(Failure::Locked);
+    assert!(matches!(
+        keyring
+            .create_item("Item", &[("app", "mock")], "secret", false)
+            .await,
+        Err(oo7::Error::File(file::Error::Locked))
+    ));
+    assert!(keyring.items().await.unwrap().is_empty());
+}
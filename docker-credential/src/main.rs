@@ -0,0 +1,203 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+};
+
+use oo7::{Keyring, XDG_SCHEMA_ATTRIBUTE};
+use serde::{Deserialize, Serialize};
+
+const SCHEMA: &str = "org.oo7.DockerCredential";
+
+const SERVER_URL_ATTRIBUTE: &str = "server_url";
+const USERNAME_ATTRIBUTE: &str = "username";
+
+/// Error messages recognized by the docker client.
+const ERR_NOT_FOUND: &str = "credentials not found in native keychain";
+const ERR_MISSING_SERVER_URL: &str = "no credentials server URL";
+const ERR_MISSING_USERNAME: &str = "no credentials username";
+
+/// A credential, as exchanged with the docker client.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Credential {
+    #[serde(rename = "ServerURL")]
+    server_url: String,
+    username: String,
+    secret: String,
+}
+
+impl Credential {
+    fn label(&self) -> String {
+        format!("Docker: {}", self.server_url)
+    }
+}
+
+/// The attributes shared by all the items of a registry.
+fn attributes(server_url: &str) -> HashMap<&'static str, String> {
+    HashMap::from([
+        (XDG_SCHEMA_ATTRIBUTE, SCHEMA.to_owned()),
+        (SERVER_URL_ATTRIBUTE, server_url.to_owned()),
+    ])
+}
+
+/// Read the server URL sent by `get` and `erase`.
+fn read_server_url(input: &str) -> Result<&str, Box<dyn std::error::Error>> {
+    let server_url = input.trim();
+    if server_url.is_empty() {
+        return Err(ERR_MISSING_SERVER_URL.into());
+    }
+    Ok(server_url)
+}
+
+async fn keyring() -> Result<Keyring, oo7::Error> {
+    let keyring = Keyring::new().await?;
+    if keyring.is_locked().await? {
+        keyring.unlock().await?;
+    }
+    Ok(keyring)
+}
+
+async fn store(input: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let credential = serde_json::from_str::<Credential>(input)?;
+    if credential.server_url.is_empty() {
+        return Err(ERR_MISSING_SERVER_URL.into());
+    }
+    if credential.username.is_empty() {
+        return Err(ERR_MISSING_USERNAME.into());
+    }
+    let mut attributes = attributes(&credential.server_url);
+
+    let keyring = keyring().await?;
+    // A single login is kept per registry
+    keyring.delete(&attributes).await?;
+    attributes.insert(USERNAME_ATTRIBUTE, credential.username.clone());
+    keyring
+        .create_item(
+            &credential.label(),
+            &attributes,
+            credential.secret.as_str(),
+            true,
+        )
+        .await?;
+    Ok(())
+}
+
+async fn get(input: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let server_url = read_server_url(input)?;
+    let keyring = keyring().await?;
+    let items = keyring.search_items(&attributes(server_url)).await?;
+    let Some(item) = items.first() else {
+        return Err(ERR_NOT_FOUND.into());
+    };
+
+    let credential = Credential {
+        server_url: server_url.to_owned(),
+        username: item
+            .attributes()
+            .await?
+            .remove(USERNAME_ATTRIBUTE)
+            .unwrap_or_default(),
+        secret: String::from_utf8(item.secret().await?.to_vec())?,
+    };
+    println!("{}", serde_json::to_string(&credential)?);
+    Ok(())
+}
+
+async fn erase(input: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let server_url = read_server_url(input)?;
+    let keyring = keyring().await?;
+    let attributes = attributes(server_url);
+    if keyring.search_items(&attributes).await?.is_empty() {
+        return Err(ERR_NOT_FOUND.into());
+    }
+    keyring.delete(&attributes).await?;
+    Ok(())
+}
+
+async fn list() -> Result<(), Box<dyn std::error::Error>> {
+    let keyring = keyring().await?;
+    let items = keyring
+        .search_items(&[(XDG_SCHEMA_ATTRIBUTE, SCHEMA)])
+        .await?;
+    let mut credentials = BTreeMap::new();
+    for item in items {
+        let mut attributes = item.attributes().await?;
+        if let Some(server_url) = attributes.remove(SERVER_URL_ATTRIBUTE) {
+            let username = attributes.remove(USERNAME_ATTRIBUTE).unwrap_or_default();
+            credentials.insert(server_url, username);
+        }
+    }
+    println!("{}", serde_json::to_string(&credentials)?);
+    Ok(())
+}
+
+fn main() -> std::process::ExitCode {
+    let action = std::env::args().nth(1);
+    match action.as_deref() {
+        Some("store" | "get" | "erase" | "list") => {}
+        Some("version") => {
+            println!("docker-credential-oo7 {}", env!("CARGO_PKG_VERSION"));
+            return std::process::ExitCode::SUCCESS;
+        }
+        _ => {
+            eprintln!("usage: docker-credential-oo7 <store|get|erase|list|version>");
+            return std::process::ExitCode::FAILURE;
+        }
+    }
+
+    let mut input = String::new();
+    if let Err(err) = std::io::stdin().read_to_string(&mut input) {
+        println!("failed to read the input: {err}");
+        return std::process::ExitCode::FAILURE;
+    }
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let result = rt.block_on(async move {
+        match action.as_deref() {
+            Some("store") => store(&input).await,
+            Some("get") => get(&input).await,
+            Some("erase") => erase(&input).await,
+            Some("list") => list().await,
+            _ => unreachable!(),
+        }
+    });
+
+    match result {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(err) => {
+            // The docker client reads the error message from stdout
+            println!("{err}");
+            std::process::ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credential_json() {
+        let input =
+            r#"{"ServerURL":"https://index.docker.io/v1/","Username":"alice","Secret":"secret"}"#;
+        let credential = serde_json::from_str::<Credential>(input).unwrap();
+        assert_eq!(credential.server_url, "https://index.docker.io/v1/");
+        assert_eq!(credential.username, "alice");
+        assert_eq!(credential.secret, "secret");
+        assert_eq!(credential.label(), "Docker: https://index.docker.io/v1/");
+        assert_eq!(serde_json::to_string(&credential).unwrap(), input);
+
+        let attributes = attributes(&credential.server_url);
+        assert_eq!(attributes[XDG_SCHEMA_ATTRIBUTE], SCHEMA);
+        assert_eq!(attributes[SERVER_URL_ATTRIBUTE], credential.server_url);
+    }
+
+    #[test]
+    fn server_url() {
+        assert_eq!(read_server_url("ghcr.io\n").unwrap(), "ghcr.io");
+        assert!(read_server_url(" \n").is_err());
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+use oo7::{XDG_SCHEMA_ATTRIBUTE, dbus::Collection};
+
+/// Schema used by `git-credential-libsecret`.
+const SCHEMA: &str = "org.gnome.keyring.NetworkPassword";
+
+const PASSWORD_EXPIRY_UTC: &str = "password_expiry_utc";
+const OAUTH_REFRESH_TOKEN: &str = "oauth_refresh_token";
+
+/// A credential description, as exchanged with git.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Credential {
+    protocol: Option<String>,
+    host: Option<String>,
+    path: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    password_expiry_utc: Option<String>,
+    oauth_refresh_token: Option<String>,
+}
+
+impl Credential {
+    /// Parse the `key=value` lines sent by git, until an empty line.
+    fn read(input: impl BufRead) -> std::io::Result<Self> {
+        let mut credential = Self::default();
+        for line in input.lines() {
+            let line = line?;
+            if line.is_empty() {
+                break;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = Some(value.to_owned());
+            match key {
+                "protocol" => credential.protocol = value,
+                "host" => credential.host = value,
+                "path" => credential.path = value,
+                "username" => credential.username = value,
+                "password" => credential.password = value,
+                PASSWORD_EXPIRY_UTC => credential.password_expiry_utc = value,
+                OAUTH_REFRESH_TOKEN => credential.oauth_refresh_token = value,
+                // Other keys, like capabilities, are not supported
+                _ => {}
+            }
+        }
+        Ok(credential)
+    }
+
+    /// The attributes of the matching items, like `git-credential-libsecret`
+    /// does.
+    fn attributes(&self) -> HashMap<&str, String> {
+        let mut attributes = HashMap::new();
+        if let Some(username) = &self.username {
+            attributes.insert("user", username.clone());
+        }
+        if let Some(host) = &self.host {
+            // The port is stored separately
+            match host.rsplit_once(':') {
+                Some((server, port)) if port.parse::<u16>().is_ok() => {
+                    attributes.insert("server", server.to_owned());
+                    attributes.insert("port", port.to_owned());
+                }
+                _ => {
+                    attributes.insert("server", host.clone());
+                }
+            }
+        }
+        if let Some(path) = &self.path {
+            attributes.insert("object", path.clone());
+        }
+        if let Some(protocol) = &self.protocol {
+            attributes.insert("protocol", protocol.clone());
+        }
+        attributes
+    }
+
+    fn label(&self) -> String {
+        format!(
+            "Git: {}://{}/{}",
+            self.protocol.as_deref().unwrap_or_default(),
+            self.host.as_deref().unwrap_or_default(),
+            self.path.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// The stored secret: the password followed by the extra fields, one per
+    /// line.
+    fn secret(&self) -> String {
+        let mut secret = self.password.clone().unwrap_or_default();
+        if let Some(expiry) = &self.password_expiry_utc {
+            secret.push_str(&format!("\n{PASSWORD_EXPIRY_UTC}={expiry}"));
+        }
+        if let Some(token) = &self.oauth_refresh_token {
+            secret.push_str(&format!("\n{OAUTH_REFRESH_TOKEN}={token}"));
+        }
+        secret
+    }
+
+    /// Fill the password and the extra fields from a stored secret.
+    fn set_secret(&mut self, secret: &str) {
+        let mut lines = secret.split('\n');
+        self.password = lines.next().map(ToOwned::to_owned);
+        for line in lines {
+            match line.split_once('=') {
+                Some((PASSWORD_EXPIRY_UTC, value)) => {
+                    self.password_expiry_utc = Some(value.to_owned())
+                }
+                Some((OAUTH_REFRESH_TOKEN, value)) => {
+                    self.oauth_refresh_token = Some(value.to_owned())
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn write(&self, mut output: impl Write) -> std::io::Result<()> {
+        let fields = [
+            ("username", &self.username),
+            ("password", &self.password),
+            (PASSWORD_EXPIRY_UTC, &self.password_expiry_utc),
+            (OAUTH_REFRESH_TOKEN, &self.oauth_refresh_token),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                writeln!(output, "{key}={value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn collection() -> Result<Collection, oo7::dbus::Error> {
+    let service = oo7::dbus::Service::new().await?;
+    let collection = service.default_collection().await?;
+    if collection.is_locked().await? {
+        collection.unlock(None).await?;
+    }
+    Ok(collection)
+}
+
+async fn get(mut credential: Credential) -> Result<(), Box<dyn std::error::Error>> {
+    let attributes = credential.attributes();
+    if attributes.is_empty() {
+        return Ok(());
+    }
+    let collection = collection().await?;
+    let items = collection.search_items(&attributes).await?;
+    let Some(item) = items.first() else {
+        return Ok(());
+    };
+
+    let secret = item.secret().await?;
+    credential.set_secret(&String::from_utf8_lossy(&secret));
+    if credential.username.is_none() {
+        credential.username = item.attributes().await?.remove("user");
+    }
+    credential.write(std::io::stdout().lock())?;
+    Ok(())
+}
+
+async fn store(credential: Credential) -> Result<(), Box<dyn std::error::Error>> {
+    // Same sanity check as git-credential-libsecret
+    if credential.protocol.is_none()
+        || (credential.host.is_none() && credential.path.is_none())
+        || credential.username.is_none()
+        || credential.password.is_none()
+    {
+        return Ok(());
+    }
+    let mut attributes = credential.attributes();
+    attributes.insert(XDG_SCHEMA_ATTRIBUTE, SCHEMA.to_owned());
+
+    let collection = collection().await?;
+    collection
+        .create_item(
+            &credential.label(),
+            &attributes,
+            credential.secret(),
+            true,
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+async fn erase(credential: Credential) -> Result<(), Box<dyn std::error::Error>> {
+    // Avoid erasing everything
+    if credential.protocol.is_none()
+        && credential.host.is_none()
+        && credential.path.is_none()
+        && credential.username.is_none()
+    {
+        return Ok(());
+    }
+    let attributes = credential.attributes();
+    let collection = collection().await?;
+    for item in collection.search_items(&attributes).await? {
+        // Only erase the rejected password, if given
+        if let Some(password) = &credential.password {
+            let mut stored = Credential::default();
+            stored.set_secret(&String::from_utf8_lossy(&item.secret().await?));
+            if stored.password.as_ref() != Some(password) {
+                continue;
+            }
+        }
+        item.delete(None).await?;
+    }
+    Ok(())
+}
+
+fn main() -> std::process::ExitCode {
+    let Some(action) = std::env::args().nth(1) else {
+        eprintln!("usage: git-credential-oo7 <get|store|erase>");
+        return std::process::ExitCode::FAILURE;
+    };
+    let credential = match Credential::read(std::io::stdin().lock()) {
+        Ok(credential) => credential,
+        Err(err) => {
+            eprintln!("git-credential-oo7: failed to read the credential: {err}");
+            return std::process::ExitCode::FAILURE;
+        }
+    };
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let result = rt.block_on(async move {
+        match action.as_str() {
+            "get" => get(credential).await,
+            "store" => store(credential).await,
+            "erase" => erase(credential).await,
+            // Unknown actions must be ignored
+            _ => Ok(()),
+        }
+    });
+
+    match result {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("git-credential-oo7: {err}");
+            std::process::ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write() {
+        let input = "protocol=https\nhost=example.com:8080\npath=repo.git\nusername=alice\npassword=secret\ncapability[]=authtype\n\nignored=value\n";
+        let credential = Credential::read(input.as_bytes()).unwrap();
+        assert_eq!(credential.protocol.as_deref(), Some("https"));
+        assert_eq!(credential.host.as_deref(), Some("example.com:8080"));
+
+        let attributes = credential.attributes();
+        assert_eq!(attributes["server"], "example.com");
+        assert_eq!(attributes["port"], "8080");
+        assert_eq!(attributes["object"], "repo.git");
+        assert_eq!(attributes["user"], "alice");
+        assert_eq!(attributes["protocol"], "https");
+
+        let mut output = Vec::new();
+        credential.write(&mut output).unwrap();
+        assert_eq!(output, b"username=alice\npassword=secret\n");
+    }
+
+    #[test]
+    fn secret_extra_fields() {
+        let credential = Credential {
+            password: Some("secret".to_owned()),
+            password_expiry_utc: Some("1700000000".to_owned()),
+            oauth_refresh_token: Some("token".to_owned()),
+            ..Default::default()
+        };
+        let secret = credential.secret();
+        assert_eq!(
+            secret,
+            "secret\npassword_expiry_utc=1700000000\noauth_refresh_token=token"
+        );
+
+        let mut stored = Credential::default();
+        stored.set_secret(&secret);
+        assert_eq!(stored, credential);
+
+        // Secrets stored by older helpers only contain the password
+        let mut stored = Credential::default();
+        stored.set_secret("secret");
+        assert_eq!(stored.password.as_deref(), Some("secret"));
+        assert!(stored.password_expiry_utc.is_none());
+    }
+}
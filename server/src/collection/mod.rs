@@ -515,6 +515,10 @@ impl Collection {
                     }
                     drop(items);
 
+                    // The items are unlocked already, they are encrypted with the
+                    // new key when locked again
+                    self.service.apply_kdf_policy(&unlocked).await;
+
                     Keyring::Unlocked(unlocked)
                 }
                 (other, _) => other,
@@ -68,6 +68,23 @@ pub fn retrieve(exchange: &str, aes_key: &Key) -> Option<oo7::Secret> {
     }
 }
 
+// Creates the final payload containing the encrypted secret, as sent by the
+// prompter in reply to the initial payload
+pub fn answer(exchange: &str, secret: &oo7::Secret) -> Result<String, crypto::Error> {
+    let private_key = Key::generate_private_key()?;
+    let public_key = crate::gnome::crypto::generate_public_key(&private_key)?;
+    let aes_key = handshake(&private_key, exchange)?;
+    let iv = crypto::generate_iv()?;
+    let encrypted = crypto::encrypt(secret.as_bytes(), &aes_key, &iv)?;
+    let map = HashMap::from([
+        (PUBLIC, public_key.as_ref()),
+        (SECRET, encrypted.as_ref()),
+        (IV, iv.as_ref()),
+    ]);
+
+    Ok(encode(&map))
+}
+
 // Converts a HashMap into a payload String
 fn encode(map: &HashMap<&str, &[u8]>) -> String {
     let mut exchange = map
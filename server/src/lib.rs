@@ -0,0 +1,19 @@
+mod collection;
+mod error;
+#[cfg(any(feature = "gnome_native_crypto", feature = "gnome_openssl_crypto"))]
+mod gnome;
+mod item;
+mod pam_listener;
+#[cfg(any(feature = "plasma_native_crypto", feature = "plasma_openssl_crypto"))]
+mod plasma;
+mod prompt;
+mod service;
+mod session;
+#[cfg(test)]
+mod tests;
+
+pub use error::Error;
+#[cfg(any(feature = "gnome_native_crypto", feature = "gnome_openssl_crypto"))]
+#[doc(hidden)]
+pub use gnome::secret_exchange;
+pub use service::Service;
@@ -1,17 +1,4 @@
 mod capability;
-mod collection;
-mod error;
-#[cfg(any(feature = "gnome_native_crypto", feature = "gnome_openssl_crypto"))]
-mod gnome;
-mod item;
-mod pam_listener;
-#[cfg(any(feature = "plasma_native_crypto", feature = "plasma_openssl_crypto"))]
-mod plasma;
-mod prompt;
-mod service;
-mod session;
-#[cfg(test)]
-mod tests;
 
 use std::{
     io::{IsTerminal, Read},
@@ -19,11 +6,10 @@ use std::{
 };
 
 use clap::Parser;
-use service::Service;
+use oo7::file::Kdf;
+use oo7_daemon::{Error, Service};
 use tokio::io::AsyncReadExt;
 
-use crate::error::Error;
-
 const BINARY_NAME: &str = env!("CARGO_BIN_NAME");
 
 #[derive(Parser)]
@@ -44,6 +30,40 @@ struct Args {
         help = "Print debug information during command processing."
     )]
     is_verbose: bool,
+    #[arg(
+        long,
+        value_name = "KIB",
+        help = "Minimum Argon2id memory cost of the keyrings, the weaker ones are re-keyed when unlocked."
+    )]
+    kdf_memory_cost: Option<u32>,
+    #[arg(
+        long,
+        value_name = "PASSES",
+        help = "Minimum Argon2id time cost of the keyrings, the weaker ones are re-keyed when unlocked."
+    )]
+    kdf_time_cost: Option<u32>,
+}
+
+impl Args {
+    /// The key derivation parameters the keyrings are re-keyed to on unlock,
+    /// if any was configured.
+    fn kdf_policy(&self) -> Option<Kdf> {
+        if self.kdf_memory_cost.is_none() && self.kdf_time_cost.is_none() {
+            return None;
+        }
+        Some(match Kdf::default() {
+            Kdf::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            } => Kdf::Argon2id {
+                memory_cost: self.kdf_memory_cost.unwrap_or(memory_cost),
+                time_cost: self.kdf_time_cost.unwrap_or(time_cost),
+                parallelism,
+            },
+            kdf => kdf,
+        })
+    }
 }
 
 /// Whether the daemon should exit if the password provided for unlocking the
@@ -95,9 +115,10 @@ async fn inner_main(args: Args) -> Result<(), Error> {
     };
 
     tracing::info!("Starting {BINARY_NAME}");
+    let kdf_policy = args.kdf_policy();
 
     if let Some((secret, should_error_out)) = secret_info {
-        let res = Service::run(Some(secret), args.replace).await;
+        let res = Service::run(Some(secret), args.replace, kdf_policy).await;
         match res {
             Ok(()) => (),
             // Wrong password provided via system credentials
@@ -117,7 +138,7 @@ async fn inner_main(args: Args) -> Result<(), Error> {
             Err(err) => Err(err)?,
         }
     } else {
-        Service::run(None, args.replace).await?;
+        Service::run(None, args.replace, kdf_policy).await?;
     }
 
     tracing::debug!("Starting loop");
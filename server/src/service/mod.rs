@@ -11,7 +11,7 @@ use oo7::{
         Algorithm, ServiceError,
         api::{DBusSecretInner, Properties},
     },
-    file::{Keyring, LockedKeyring, UnlockedKeyring},
+    file::{Kdf, Keyring, LockedKeyring, UnlockedKeyring},
 };
 use tokio::sync::{Mutex, RwLock};
 use tokio_stream::StreamExt;
@@ -52,6 +52,13 @@ pub struct Service {
     #[allow(clippy::type_complexity)]
     pub(crate) pending_migrations:
         Arc<Mutex<HashMap<String, (std::path::PathBuf, String, String)>>>,
+    // key derivation parameters the weaker keyrings are re-keyed to on unlock
+    pub(crate) kdf_policy: Option<Kdf>,
+    // where the collections created through a prompt are written, the data
+    // directory if unset
+    keyrings_dir: Option<std::path::PathBuf>,
+    // whether the clients are connected peer-to-peer, without unique names
+    pub(crate) peer_to_peer: bool,
 }
 
 #[zbus::interface(name = "org.freedesktop.Secret.Service")]
@@ -90,17 +97,12 @@ impl Service {
 
         let sender = if let Some(s) = header.sender() {
             s.to_owned()
+        } else if self.peer_to_peer {
+            // Peer-to-peer connections, used when the service is embedded, don't
+            // have a bus to assign unique names
+            UniqueName::try_from(":p2p.peer").unwrap()
         } else {
-            #[cfg(test)]
-            {
-                // For p2p test connections, use a dummy sender since p2p connections
-                // don't have a bus to assign unique names
-                UniqueName::try_from(":p2p.test").unwrap().into()
-            }
-            #[cfg(not(test))]
-            {
-                return Err(custom_service_error("Failed to get sender from header."));
-            }
+            return Err(custom_service_error("Failed to get sender from header."));
         };
 
         tracing::info!("Client {} connected", sender);
@@ -391,8 +393,15 @@ impl Service {
 impl Service {
     const LOGIN_ALIAS: &str = "login";
 
-    pub async fn run(secret: Option<Secret>, request_replacement: bool) -> Result<(), Error> {
-        let service = Self::default();
+    pub async fn run(
+        secret: Option<Secret>,
+        request_replacement: bool,
+        kdf_policy: Option<Kdf>,
+    ) -> Result<(), Error> {
+        let service = Self {
+            kdf_policy,
+            ..Self::default()
+        };
 
         let connection = zbus::connection::Builder::session()?
             .allow_name_replacements(true)
@@ -438,7 +447,39 @@ impl Service {
         connection: zbus::Connection,
         secret: Option<Secret>,
     ) -> Result<Self, Error> {
-        let service = Self::default();
+        let default_keyring = if let Some(secret) = secret {
+            vec![(
+                "Login".to_owned(),
+                oo7::dbus::Service::DEFAULT_COLLECTION.to_owned(),
+                Keyring::Unlocked(UnlockedKeyring::temporary(secret).await?),
+            )]
+        } else {
+            vec![]
+        };
+
+        Self::run_with_keyrings(connection, default_keyring, None).await
+    }
+
+    /// Serve the service on an existing connection, with the given (label,
+    /// alias, keyring) collections instead of the ones found on disk.
+    ///
+    /// The collections created through a prompt are written to
+    /// `keyrings_dir`, or to the data directory if unset.
+    ///
+    /// The connection is expected to own the service name already, if it is
+    /// a bus connection. Used to embed the service, in integration tests for
+    /// example.
+    pub async fn run_with_keyrings(
+        connection: zbus::Connection,
+        keyrings: Vec<(String, String, Keyring)>,
+        keyrings_dir: Option<std::path::PathBuf>,
+    ) -> Result<Self, Error> {
+        let service = Self {
+            keyrings_dir,
+            // Only bus connections have a unique name
+            peer_to_peer: connection.unique_name().is_none(),
+            ..Self::default()
+        };
 
         // Serve the service at the standard path
         connection
@@ -458,19 +499,7 @@ impl Service {
             )
             .await?;
 
-        let default_keyring = if let Some(secret) = secret {
-            vec![(
-                "Login".to_owned(),
-                oo7::dbus::Service::DEFAULT_COLLECTION.to_owned(),
-                Keyring::Unlocked(UnlockedKeyring::temporary(secret).await?),
-            )]
-        } else {
-            vec![]
-        };
-
-        service
-            .initialize(connection, default_keyring, false)
-            .await?;
+        service.initialize(connection, keyrings, false).await?;
         Ok(service)
     }
 
@@ -501,10 +530,10 @@ impl Service {
 
         let keyrings_dir = data_dir.join("keyrings");
 
-        // Scan for v1 keyrings first
+        // Scan for v1 keyrings first, the v2 keyrings are stored alongside them
         let v1_dir = keyrings_dir.join("v1");
         if v1_dir.exists() {
-            tracing::debug!("Scanning for v1 keyrings in {}", v1_dir.display());
+            tracing::debug!("Scanning for v1 and v2 keyrings in {}", v1_dir.display());
             if let Ok(mut entries) = tokio::fs::read_dir(&v1_dir).await {
                 while let Ok(Some(entry)) = entries.next_entry().await {
                     let path = entry.path();
@@ -515,7 +544,7 @@ impl Service {
                     }
 
                     if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
-                        tracing::debug!("Found v1 keyring: {name}");
+                        tracing::debug!("Found keyring: {name}");
 
                         // Try to load the keyring
                         match self.load_keyring(&path, name, secret.as_ref()).await {
@@ -567,6 +596,27 @@ impl Service {
         Ok(discovered)
     }
 
+    /// Re-key `keyring` if its key derivation parameters are weaker than the
+    /// configured policy, so the old files get hardened over time.
+    pub(crate) async fn apply_kdf_policy(&self, keyring: &UnlockedKeyring) {
+        let Some(policy) = self.kdf_policy else {
+            return;
+        };
+        // Temporary keyrings are never written to disk
+        let Some(path) = keyring.path() else {
+            return;
+        };
+        let kdf = keyring.kdf().await;
+        if !is_below_policy(kdf, policy) {
+            return;
+        }
+
+        tracing::info!("Re-keying {} from {kdf} to {policy}", path.display());
+        if let Err(err) = keyring.rekey(policy).await {
+            tracing::warn!("Failed to re-key {}: {err}", path.display());
+        }
+    }
+
     /// Load a single keyring from a file path
     /// Returns (label, alias, keyring)
     async fn load_keyring(
@@ -593,11 +643,14 @@ impl Service {
         // Try to load the keyring
         let keyring = match LockedKeyring::load(path).await {
             Ok(locked_keyring) => {
-                // Successfully loaded as v1 keyring
+                // Successfully loaded as v1 or v2 keyring
+                let (major, minor) = locked_keyring.version().await;
+                tracing::debug!("Loaded keyring '{name}' in format version {major}.{minor}");
                 if let Some(secret) = secret {
                     match locked_keyring.unlock(secret.clone()).await {
                         Ok(unlocked) => {
                             tracing::info!("Unlocked keyring '{}' from {:?}", name, path);
+                            self.apply_kdf_policy(&unlocked).await;
                             Keyring::Unlocked(unlocked)
                         }
                         Err(e) => {
@@ -930,9 +983,14 @@ impl Service {
         secret: Secret,
     ) -> Result<OwnedObjectPath, ServiceError> {
         // Create a persistent keyring with the provided secret
-        let keyring = UnlockedKeyring::open(&label.to_lowercase(), secret)
-            .await
-            .map_err(|err| custom_service_error(&format!("Failed to create keyring: {err}")))?;
+        let name = label.to_lowercase();
+        let keyring = match &self.keyrings_dir {
+            Some(dir) => {
+                UnlockedKeyring::load(dir.join("v1").join(format!("{name}.keyring")), secret).await
+            }
+            None => UnlockedKeyring::open(&name, secret).await,
+        }
+        .map_err(|err| custom_service_error(&format!("Failed to create keyring: {err}")))?;
 
         // Write the keyring file to disk immediately
         keyring
@@ -1176,5 +1234,31 @@ impl Service {
     }
 }
 
+/// Whether `kdf` is weaker than `policy`.
+fn is_below_policy(kdf: Kdf, policy: Kdf) -> bool {
+    match (kdf, policy) {
+        (
+            Kdf::Pbkdf2 { iteration_count },
+            Kdf::Pbkdf2 {
+                iteration_count: min_iteration_count,
+            },
+        ) => iteration_count < min_iteration_count,
+        (Kdf::Pbkdf2 { .. }, Kdf::Argon2id { .. }) => true,
+        (Kdf::Argon2id { .. }, Kdf::Pbkdf2 { .. }) => false,
+        (
+            Kdf::Argon2id {
+                memory_cost,
+                time_cost,
+                ..
+            },
+            Kdf::Argon2id {
+                memory_cost: min_memory_cost,
+                time_cost: min_time_cost,
+                ..
+            },
+        ) => memory_cost < min_memory_cost || time_cost < min_time_cost,
+    }
+}
+
 #[cfg(test)]
 mod tests;
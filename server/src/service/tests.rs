@@ -1257,6 +1257,117 @@ async fn discover_v1_keyrings() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[tokio::test]
+#[serial_test::serial(xdg_env)]
+async fn discover_v2_keyrings() -> Result<(), Box<dyn std::error::Error>> {
+    let service = Service::default();
+    let temp_dir = tempfile::tempdir()?;
+    unsafe { std::env::set_var("XDG_DATA_HOME", temp_dir.path()) };
+
+    let v1_dir = temp_dir.path().join("keyrings/v1");
+    tokio::fs::create_dir_all(&v1_dir).await?;
+
+    // Copy the existing v1 keyring fixture
+    let secret = Secret::from("test");
+    let fixture_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("client/fixtures/default.keyring");
+    tokio::fs::copy(&fixture_path, v1_dir.join("default.keyring")).await?;
+
+    // New keyrings are written in the v2 format, next to the v1 ones
+    let v2_keyring = UnlockedKeyring::open("modern", secret.clone()).await?;
+    v2_keyring
+        .create_item(
+            "V2 Item",
+            &[("type", "v2")],
+            Secret::text("v2-secret"),
+            false,
+        )
+        .await?;
+    v2_keyring.write().await?;
+    let v2_path = v1_dir.join("modern.keyring");
+    assert_eq!(LockedKeyring::load(&v2_path).await?.version().await, (2, 0));
+
+    let discovered = service.discover_keyrings(None).await?;
+    assert_eq!(discovered.len(), 2, "Should discover both keyrings");
+    for (_, _, keyring) in &discovered {
+        assert!(keyring.is_locked());
+    }
+
+    let discovered = service.discover_keyrings(Some(secret)).await?;
+    assert_eq!(discovered.len(), 2, "Should discover both keyrings");
+    for (label, _, keyring) in &discovered {
+        assert!(!keyring.is_locked(), "{label} should be unlocked");
+    }
+
+    unsafe { std::env::remove_var("XDG_DATA_HOME") };
+    Ok(())
+}
+
+#[tokio::test]
+#[serial_test::serial(xdg_env)]
+async fn rekey_with_kdf_policy() -> Result<(), Box<dyn std::error::Error>> {
+    let policy = Kdf::Argon2id {
+        memory_cost: 32 * 1024,
+        time_cost: 3,
+        parallelism: 1,
+    };
+    let service = Service {
+        kdf_policy: Some(policy),
+        ..Default::default()
+    };
+    let temp_dir = tempfile::tempdir()?;
+    unsafe { std::env::set_var("XDG_DATA_HOME", temp_dir.path()) };
+
+    let v1_dir = temp_dir.path().join("keyrings/v1");
+    tokio::fs::create_dir_all(&v1_dir).await?;
+
+    // A v1 keyring and a v2 one using the default parameters, both weaker
+    let secret = Secret::from("test");
+    let fixture_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("client/fixtures/default.keyring");
+    tokio::fs::copy(&fixture_path, v1_dir.join("default.keyring")).await?;
+    let keyring = UnlockedKeyring::open("modern", secret.clone()).await?;
+    keyring
+        .create_item("Item", &[("type", "v2")], Secret::text("v2-secret"), false)
+        .await?;
+    assert!(is_below_policy(keyring.kdf().await, policy));
+
+    // Kept as is while locked
+    let discovered = service.discover_keyrings(None).await?;
+    assert_eq!(discovered.len(), 2);
+    for name in ["default", "modern"] {
+        let path = v1_dir.join(format!("{name}.keyring"));
+        assert_ne!(LockedKeyring::load(&path).await?.kdf().await, policy);
+    }
+
+    let discovered = service.discover_keyrings(Some(secret.clone())).await?;
+    assert_eq!(discovered.len(), 2);
+    for name in ["default", "modern"] {
+        let path = v1_dir.join(format!("{name}.keyring"));
+        let locked = LockedKeyring::load(&path).await?;
+        assert_eq!(locked.kdf().await, policy, "{name} should be re-keyed");
+        let keyring = locked.unlock(secret.clone()).await?;
+        assert!(!keyring.items().await?.is_empty());
+    }
+
+    // Keyrings stronger than the policy are kept as is
+    assert!(!is_below_policy(
+        Kdf::Argon2id {
+            memory_cost: 64 * 1024,
+            time_cost: 3,
+            parallelism: 4,
+        },
+        policy
+    ));
+
+    unsafe { std::env::remove_var("XDG_DATA_HOME") };
+    Ok(())
+}
+
 #[tokio::test]
 #[serial_test::serial(xdg_env)]
 async fn discover_v0_keyrings() -> Result<(), Box<dyn std::error::Error>> {
@@ -1,8 +1,6 @@
 use std::{collections::HashMap, fs::File, io::Write, sync::Arc};
 
-#[cfg(any(feature = "gnome_native_crypto", feature = "gnome_openssl_crypto"))]
-use base64::Engine;
-use oo7::{Secret, crypto, dbus};
+use oo7::{Secret, dbus};
 use rustix::net::{AddressFamily, SocketFlags, SocketType, socketpair};
 use tokio_stream::StreamExt;
 use zbus::zvariant::{Fd, ObjectPath, Optional, Value};
@@ -243,7 +241,8 @@ impl TestServiceSetup {
 
         let (server_conn, client_conn) = create_p2p_connection().await?;
 
-        let service = crate::Service::default();
+        let mut service = crate::Service::default();
+        service.peer_to_peer = true;
 
         server_conn
             .object_server()
@@ -470,24 +469,7 @@ impl MockPrompterService {
                 };
                 drop(queue);
 
-                // Generate our own key pair
-                let private_key = oo7::Key::generate_private_key().unwrap();
-                let public_key = crate::gnome::crypto::generate_public_key(&private_key).unwrap();
-
-                // Handshake with server's exchange to get AES key
-                let aes_key = secret_exchange::handshake(&private_key, &exchange).unwrap();
-
-                // Encrypt the password
-                let iv = crypto::generate_iv().unwrap();
-                let encrypted = crypto::encrypt(password.as_bytes(), &aes_key, &iv).unwrap();
-
-                // Create final exchange with encrypted secret
-                let final_exchange = format!(
-                    "[sx-aes-1]\npublic={}\nsecret={}\niv={}",
-                    base64::prelude::BASE64_STANDARD.encode(public_key.as_ref()),
-                    base64::prelude::BASE64_STANDARD.encode(&encrypted),
-                    base64::prelude::BASE64_STANDARD.encode(&iv)
-                );
+                let final_exchange = secret_exchange::answer(&exchange, &password).unwrap();
 
                 tracing::debug!("MockPrompter: calling PromptReady with yes");
                 connection
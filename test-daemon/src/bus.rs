@@ -0,0 +1,69 @@
+use std::process::Stdio;
+
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::{Child, Command},
+};
+
+use crate::Error;
+
+const CONFIG: &str = r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:dir={dir}</listen>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#;
+
+/// A `dbus-daemon` instance, stopped when dropped.
+#[derive(Debug)]
+pub(crate) struct PrivateBus {
+    address: String,
+    _child: Child,
+    _dir: tempfile::TempDir,
+}
+
+impl PrivateBus {
+    pub(crate) async fn start() -> Result<Self, Error> {
+        // Without activatable services, nothing else can own the names
+        let dir = tempfile::tempdir()?;
+        let config = dir.path().join("bus.conf");
+        let dir_path = dir.path().display().to_string();
+        tokio::fs::write(&config, CONFIG.replace("{dir}", &dir_path)).await?;
+
+        let mut child = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let mut address = String::new();
+        let stdout = child.stdout.take().unwrap();
+        BufReader::new(stdout).read_line(&mut address).await?;
+        let address = address.trim().to_owned();
+        if address.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "dbus-daemon exited without an address",
+            )
+            .into());
+        }
+
+        Ok(Self {
+            address,
+            _child: child,
+            _dir: dir,
+        })
+    }
+
+    pub(crate) fn address(&self) -> &str {
+        &self.address
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    // File backend error, while seeding a collection
+    File(oo7::file::Error),
+    // Zbus error
+    Zbus(zbus::Error),
+    // Daemon error
+    Daemon(oo7_daemon::Error),
+    // IO error, while starting the bus
+    IO(std::io::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl From<oo7::file::Error> for Error {
+    fn from(err: oo7::file::Error) -> Self {
+        Self::File(err)
+    }
+}
+
+impl From<zbus::Error> for Error {
+    fn from(err: zbus::Error) -> Self {
+        Self::Zbus(err)
+    }
+}
+
+impl From<oo7_daemon::Error> for Error {
+    fn from(err: oo7_daemon::Error) -> Self {
+        Self::Daemon(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::IO(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(err) => write!(f, "File backend error {err}"),
+            Self::Zbus(err) => write!(f, "Zbus error {err}"),
+            Self::Daemon(err) => write!(f, "Daemon error {err}"),
+            Self::IO(err) => write!(f, "IO error {err}"),
+        }
+    }
+}
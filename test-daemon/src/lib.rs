@@ -0,0 +1,245 @@
+#![doc = include_str!("../README.md")]
+
+use oo7::{AsAttributes, Secret, file};
+
+mod bus;
+mod error;
+mod prompter;
+
+use bus::PrivateBus;
+pub use error::Error;
+pub use prompter::{Prompter, Reply};
+
+const SERVICE_NAME: &str = "org.freedesktop.secrets";
+
+/// How the applications reach the daemon.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Bus {
+    /// A peer-to-peer connection, see [`TestDaemon::connection`].
+    #[default]
+    PeerToPeer,
+    /// A private `dbus-daemon`, see [`TestDaemon::address`].
+    ///
+    /// Useful to test applications running in another process.
+    DBusDaemon,
+}
+
+/// A collection to create when the daemon starts.
+///
+/// The collections only live in memory, unlike the ones created through a
+/// prompt which are written to [`TestDaemon::keyrings_dir`].
+#[derive(Debug, Clone)]
+pub struct Collection {
+    label: String,
+    alias: Option<String>,
+    secret: Secret,
+    is_locked: bool,
+    items: Vec<file::ItemDefinition>,
+}
+
+impl Collection {
+    /// An unlocked collection, protected by `secret`.
+    pub fn new(label: &str, secret: impl Into<Secret>) -> Self {
+        Self {
+            label: label.to_owned(),
+            alias: None,
+            secret: secret.into(),
+            is_locked: false,
+            items: Vec::new(),
+        }
+    }
+
+    /// The default collection, labeled `Login`.
+    pub fn login(secret: impl Into<Secret>) -> Self {
+        Self::new("Login", secret).alias(oo7::dbus::Service::DEFAULT_COLLECTION)
+    }
+
+    /// Set the collection alias, its lowercase label by default.
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_owned());
+        self
+    }
+
+    /// Start with the collection locked.
+    ///
+    /// Unlocking it prompts for its secret.
+    pub fn locked(mut self) -> Self {
+        self.is_locked = true;
+        self
+    }
+
+    /// Add an item.
+    pub fn item(
+        mut self,
+        label: &str,
+        attributes: &impl AsAttributes,
+        secret: impl Into<Secret>,
+    ) -> Self {
+        self.items.push((
+            label.to_owned(),
+            attributes.as_attributes(),
+            secret.into(),
+            false,
+        ));
+        self
+    }
+
+    async fn into_keyring(self) -> Result<(String, String, file::Keyring), Error> {
+        let keyring = file::UnlockedKeyring::temporary(self.secret).await?;
+        keyring.create_items(self.items).await?;
+        let keyring = if self.is_locked {
+            file::Keyring::Locked(keyring.lock())
+        } else {
+            file::Keyring::Unlocked(keyring)
+        };
+        let alias = self.alias.unwrap_or_else(|| self.label.to_lowercase());
+        Ok((self.label, alias, keyring))
+    }
+}
+
+/// A [`TestDaemon`] builder.
+#[derive(Debug, Default)]
+pub struct Builder {
+    bus: Bus,
+    collections: Vec<Collection>,
+}
+
+impl Builder {
+    /// Set how the applications reach the daemon, peer-to-peer by default.
+    pub fn bus(mut self, bus: Bus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Add a collection.
+    pub fn collection(mut self, collection: Collection) -> Self {
+        self.collections.push(collection);
+        self
+    }
+
+    /// Start the daemon.
+    pub async fn build(self) -> Result<TestDaemon, Error> {
+        let mut keyrings = Vec::with_capacity(self.collections.len());
+        for collection in self.collections {
+            keyrings.push(collection.into_keyring().await?);
+        }
+        let prompter = Prompter::default();
+
+        let (bus, server_conn, connection) = match self.bus {
+            Bus::PeerToPeer => {
+                let guid = zbus::Guid::generate();
+                let (p0, p1) = tokio::net::UnixStream::pair()?;
+                let (client_conn, server_conn) = tokio::try_join!(
+                    zbus::connection::Builder::unix_stream(p0).p2p().build(),
+                    zbus::connection::Builder::unix_stream(p1)
+                        .server(guid)?
+                        .p2p()
+                        .build(),
+                )?;
+                // The daemon calls the prompter on the same connection
+                prompter.serve(&client_conn).await?;
+                (None, server_conn, client_conn)
+            }
+            Bus::DBusDaemon => {
+                let bus = PrivateBus::start().await?;
+                let server_conn = zbus::connection::Builder::address(bus.address())?
+                    .name(SERVICE_NAME)?
+                    .build()
+                    .await?;
+                let prompter_conn = zbus::connection::Builder::address(bus.address())?
+                    .name(prompter::NAME)?
+                    .build()
+                    .await?;
+                prompter.serve(&prompter_conn).await?;
+                (Some(bus), server_conn, prompter_conn)
+            }
+        };
+
+        let keyrings_dir = tempfile::tempdir()?;
+        let service = oo7_daemon::Service::run_with_keyrings(
+            server_conn,
+            keyrings,
+            Some(keyrings_dir.path().to_owned()),
+        )
+        .await?;
+
+        Ok(TestDaemon {
+            _service: service,
+            connection,
+            prompter,
+            bus,
+            keyrings_dir,
+        })
+    }
+}
+
+/// A Secret Service daemon running in the current process.
+///
+/// The daemon stops when dropped.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use oo7_test_daemon::{Collection, Reply, TestDaemon};
+///
+/// let daemon = TestDaemon::builder()
+///     .collection(
+///         Collection::login("password")
+///             .item("Token", &[("service", "example")], "secret")
+///             .locked(),
+///     )
+///     .build()
+///     .await?;
+/// daemon.prompter().queue(Reply::Accept("password".into()));
+///
+/// let service = daemon.service().await?;
+/// let collection = service.default_collection().await?;
+/// collection.unlock(None).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TestDaemon {
+    _service: oo7_daemon::Service,
+    connection: zbus::Connection,
+    prompter: Prompter,
+    bus: Option<PrivateBus>,
+    keyrings_dir: tempfile::TempDir,
+}
+
+impl TestDaemon {
+    /// Create a new builder.
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// A connection to the daemon.
+    ///
+    /// With [`Bus::DBusDaemon`], it is the connection of the prompter, which
+    /// can be used to reach the daemon too.
+    pub fn connection(&self) -> &zbus::Connection {
+        &self.connection
+    }
+
+    /// The address of the private bus, with [`Bus::DBusDaemon`].
+    ///
+    /// To be used as `DBUS_SESSION_BUS_ADDRESS` by the applications to test.
+    pub fn address(&self) -> Option<&str> {
+        self.bus.as_ref().map(PrivateBus::address)
+    }
+
+    /// The temporary directory the collections created through a prompt are
+    /// written to, removed when the daemon is dropped.
+    pub fn keyrings_dir(&self) -> &std::path::Path {
+        self.keyrings_dir.path()
+    }
+
+    /// The prompter shown for unlocking or creating a collection.
+    pub fn prompter(&self) -> &Prompter {
+        &self.prompter
+    }
+
+    /// Create a [`oo7::dbus::Service`] using [`Self::connection`].
+    pub async fn service(&self) -> Result<oo7::dbus::Service, oo7::dbus::Error> {
+        oo7::dbus::Service::new_with_connection(&self.connection).await
+    }
+}
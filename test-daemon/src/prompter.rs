@@ -0,0 +1,169 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use oo7::Secret;
+use oo7_daemon::secret_exchange;
+use zbus::{
+    message::Header,
+    names::OwnedUniqueName,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+};
+
+pub(crate) const PATH: &str = "/org/gnome/keyring/Prompter";
+pub(crate) const NAME: &str = "org.gnome.keyring.SystemPrompter";
+const CALLBACK_INTERFACE: &str = "org.gnome.keyring.internal.Prompter.Callback";
+
+/// The user interaction to simulate on a prompt.
+#[derive(Debug, Clone)]
+pub enum Reply {
+    /// Enter the secret and confirm.
+    ///
+    /// It is the new secret when creating a collection or changing its
+    /// secret. A confirmation prompt is accepted, without using the secret.
+    Accept(Secret),
+    /// Enter a wrong secret when unlocking a collection.
+    ///
+    /// The daemon prompts again, which uses the next reply. Any other prompt,
+    /// creating a collection, changing its secret or a confirmation, is
+    /// declined like with [`Reply::Dismiss`].
+    Reject,
+    /// Cancel the prompt.
+    Dismiss,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    replies: VecDeque<Reply>,
+    n_prompts: usize,
+}
+
+/// A scriptable replacement of the GNOME system prompter.
+///
+/// The replies are used in the order they are queued, one per prompt. Prompts
+/// are dismissed once the queue is empty.
+#[derive(Debug, Clone, Default)]
+pub struct Prompter {
+    state: Arc<Mutex<State>>,
+}
+
+impl Prompter {
+    /// Queue the reply to the next prompt.
+    pub fn queue(&self, reply: Reply) {
+        self.state().replies.push_back(reply);
+    }
+
+    /// The number of prompts shown so far, including the retries.
+    pub fn n_prompts(&self) -> usize {
+        self.state().n_prompts
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    fn next_reply(&self) -> Reply {
+        let mut state = self.state();
+        state.n_prompts += 1;
+        state.replies.pop_front().unwrap_or(Reply::Dismiss)
+    }
+
+    pub(crate) async fn serve(&self, connection: &zbus::Connection) -> zbus::Result<()> {
+        connection
+            .object_server()
+            .at(PATH, Interface(self.clone()))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Whether the prompt asks for a new secret, to create a collection or change
+/// its secret.
+fn is_password_new(properties: &HashMap<String, OwnedValue>) -> bool {
+    properties
+        .get("password-new")
+        .is_some_and(|value| match &**value {
+            Value::Bool(new) => *new,
+            Value::Value(value) => matches!(**value, Value::Bool(true)),
+            _ => false,
+        })
+}
+
+/// Reply to the daemon's callback, asynchronously like the real prompter.
+fn prompt_ready(
+    connection: &zbus::Connection,
+    header: &Header<'_>,
+    callback: ObjectPath<'_>,
+    reply: &'static str,
+    exchange: String,
+) {
+    let connection = connection.clone();
+    // There is no sender on a peer-to-peer connection
+    let destination = header
+        .sender()
+        .map(|sender| OwnedUniqueName::from(sender.to_owned()));
+    let callback = OwnedObjectPath::from(callback.into_owned());
+    tokio::spawn(async move {
+        let properties = HashMap::<&str, Value<'_>>::new();
+        connection
+            .call_method(
+                destination.as_ref(),
+                &callback,
+                Some(CALLBACK_INTERFACE),
+                "PromptReady",
+                &(reply, properties, exchange.as_str()),
+            )
+            .await
+    });
+}
+
+struct Interface(Prompter);
+
+#[zbus::interface(name = "org.gnome.keyring.internal.Prompter")]
+impl Interface {
+    async fn begin_prompting(
+        &self,
+        callback: ObjectPath<'_>,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) {
+        // An empty reply asks the daemon for the prompt properties
+        prompt_ready(connection, &header, callback, "", String::new());
+    }
+
+    async fn perform_prompt(
+        &self,
+        callback: ObjectPath<'_>,
+        type_: &str,
+        properties: HashMap<String, OwnedValue>,
+        exchange: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> zbus::fdo::Result<()> {
+        let failed = |err: oo7::crypto::Error| zbus::fdo::Error::Failed(err.to_string());
+        let (reply, exchange) = match (type_, self.0.next_reply()) {
+            (_, Reply::Dismiss) => ("no", String::new()),
+            ("confirm", Reply::Accept(_)) => ("yes", String::new()),
+            (_, Reply::Accept(secret)) => (
+                "yes",
+                secret_exchange::answer(exchange, &secret).map_err(failed)?,
+            ),
+            // Only an unlock prompt can be answered with a wrong secret
+            ("confirm", Reply::Reject) => ("no", String::new()),
+            (_, Reply::Reject) if is_password_new(&properties) => ("no", String::new()),
+            (_, Reply::Reject) => {
+                let secret =
+                    Secret::random().map_err(|err| zbus::fdo::Error::Failed(err.to_string()))?;
+                (
+                    "yes",
+                    secret_exchange::answer(exchange, &secret).map_err(failed)?,
+                )
+            }
+        };
+        prompt_ready(connection, &header, callback, reply, exchange);
+        Ok(())
+    }
+
+    async fn stop_prompting(&self, _callback: ObjectPath<'_>) {}
+}
@@ -0,0 +1,237 @@
+use oo7_test_daemon::{Bus, Collection, Reply, TestDaemon};
+
+#[tokio::test]
+async fn seeded_items() {
+    let daemon = TestDaemon::builder()
+        .collection(Collection::login("password").item(
+            "Token",
+            &[("service", "example")],
+            "secret",
+        ))
+        .collection(Collection::new("Work", "work-password"))
+        .build()
+        .await
+        .unwrap();
+
+    let service = daemon.service().await.unwrap();
+    let collection = service.default_collection().await.unwrap();
+    assert!(!collection.is_locked().await.unwrap());
+    let items = collection
+        .search_items(&[("service", "example")])
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].label().await.unwrap(), "Token");
+    assert_eq!(&*items[0].secret().await.unwrap(), b"secret");
+
+    let work = service.with_alias("work").await.unwrap().unwrap();
+    assert_eq!(work.label().await.unwrap(), "Work");
+    assert!(work.items().await.unwrap().is_empty());
+    assert_eq!(daemon.prompter().n_prompts(), 0);
+}
+
+#[tokio::test]
+async fn unlock_prompt() {
+    let daemon = TestDaemon::builder()
+        .collection(
+            Collection::login("password")
+                .item("Token", &[("service", "example")], "secret")
+                .locked(),
+        )
+        .build()
+        .await
+        .unwrap();
+    let service = daemon.service().await.unwrap();
+    let collection = service.default_collection().await.unwrap();
+    assert!(collection.is_locked().await.unwrap());
+
+    daemon.prompter().queue(Reply::Dismiss);
+    assert!(matches!(
+        collection.unlock(None).await,
+        Err(oo7::dbus::Error::Dismissed)
+    ));
+    assert!(collection.is_locked().await.unwrap());
+    assert_eq!(daemon.prompter().n_prompts(), 1);
+
+    // A wrong secret is asked again
+    daemon.prompter().queue(Reply::Reject);
+    daemon.prompter().queue(Reply::Accept("password".into()));
+    collection.unlock(None).await.unwrap();
+    assert!(!collection.is_locked().await.unwrap());
+    assert_eq!(daemon.prompter().n_prompts(), 3);
+
+    let items = collection.items().await.unwrap();
+    assert_eq!(&*items[0].secret().await.unwrap(), b"secret");
+}
+
+#[tokio::test]
+async fn private_bus() {
+    let daemon = TestDaemon::builder()
+        .bus(Bus::DBusDaemon)
+        .collection(Collection::login("password").locked())
+        .build()
+        .await
+        .unwrap();
+    let address = daemon.address().unwrap();
+
+    // Like an application started with DBUS_SESSION_BUS_ADDRESS
+    let connection = zbus::connection::Builder::address(address)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let service = oo7::dbus::Service::new_with_connection(&connection)
+        .await
+        .unwrap();
+    let collection = service.default_collection().await.unwrap();
+
+    daemon.prompter().queue(Reply::Accept("password".into()));
+    collection.unlock(None).await.unwrap();
+    collection
+        .create_item("Token", &[("service", "example")], "secret", true, None)
+        .await
+        .unwrap();
+
+    let service = daemon.service().await.unwrap();
+    let collection = service.default_collection().await.unwrap();
+    assert_eq!(collection.items().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn change_secret() {
+    let daemon = TestDaemon::builder()
+        .collection(
+            Collection::login("password")
+                .item("Token", &[("service", "example")], "secret")
+                .locked(),
+        )
+        .build()
+        .await
+        .unwrap();
+    let service = daemon.service().await.unwrap();
+    let collection = service.default_collection().await.unwrap();
+
+    // The collection is unlocked with the current secret first
+    collection
+        .change_secret("password", "new-password")
+        .await
+        .unwrap();
+    assert!(!collection.is_locked().await.unwrap());
+    assert_eq!(daemon.prompter().n_prompts(), 0);
+
+    // The old secret is asked again
+    collection.lock(None).await.unwrap();
+    daemon.prompter().queue(Reply::Accept("password".into()));
+    daemon
+        .prompter()
+        .queue(Reply::Accept("new-password".into()));
+    collection.unlock(None).await.unwrap();
+    assert_eq!(daemon.prompter().n_prompts(), 2);
+
+    collection.lock(None).await.unwrap();
+    assert!(
+        collection
+            .change_secret("password", "newer-password")
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn change_secret_with_prompt() {
+    let daemon = TestDaemon::builder()
+        .collection(Collection::login("password").item(
+            "Token",
+            &[("service", "example")],
+            "secret",
+        ))
+        .build()
+        .await
+        .unwrap();
+    let service = daemon.service().await.unwrap();
+    let collection = service.default_collection().await.unwrap();
+
+    daemon.prompter().queue(Reply::Dismiss);
+    assert!(matches!(
+        collection.change_secret_with_prompt(None).await,
+        Err(oo7::dbus::Error::Dismissed)
+    ));
+
+    daemon
+        .prompter()
+        .queue(Reply::Accept("new-password".into()));
+    collection.change_secret_with_prompt(None).await.unwrap();
+    assert_eq!(daemon.prompter().n_prompts(), 2);
+
+    collection.lock(None).await.unwrap();
+    daemon.prompter().queue(Reply::Accept("password".into()));
+    daemon
+        .prompter()
+        .queue(Reply::Accept("new-password".into()));
+    collection.unlock(None).await.unwrap();
+    assert!(!collection.is_locked().await.unwrap());
+    assert_eq!(daemon.prompter().n_prompts(), 4);
+
+    let items = collection.items().await.unwrap();
+    assert_eq!(&*items[0].secret().await.unwrap(), b"secret");
+}
+
+#[tokio::test]
+async fn create_collection_prompt() {
+    let daemon = TestDaemon::builder()
+        .collection(Collection::login("password"))
+        .build()
+        .await
+        .unwrap();
+    let service = daemon.service().await.unwrap();
+    let n_collections = service.collections().await.unwrap().len();
+
+    daemon.prompter().queue(Reply::Dismiss);
+    assert!(matches!(
+        service.create_collection("Work", Some("work"), None).await,
+        Err(oo7::dbus::Error::Dismissed)
+    ));
+    assert_eq!(service.collections().await.unwrap().len(), n_collections);
+
+    // There is no wrong secret for a new collection
+    daemon.prompter().queue(Reply::Reject);
+    assert!(matches!(
+        service.create_collection("Work", Some("work"), None).await,
+        Err(oo7::dbus::Error::Dismissed)
+    ));
+    assert_eq!(service.collections().await.unwrap().len(), n_collections);
+
+    daemon
+        .prompter()
+        .queue(Reply::Accept("work-password".into()));
+    let collection = service
+        .create_collection("Work", Some("work"), None)
+        .await
+        .unwrap();
+    assert_eq!(daemon.prompter().n_prompts(), 3);
+    assert_eq!(collection.label().await.unwrap(), "Work");
+    assert!(!collection.is_locked().await.unwrap());
+    collection
+        .create_item("Token", &[("service", "example")], "secret", true, None)
+        .await
+        .unwrap();
+
+    let work = service.with_alias("work").await.unwrap().unwrap();
+    assert_eq!(work.path(), collection.path());
+    assert_eq!(
+        service.collections().await.unwrap().len(),
+        n_collections + 1
+    );
+
+    // Written to the temporary directory of the daemon
+    let path = daemon.keyrings_dir().join("v1/work.keyring");
+    let keyring = oo7::file::UnlockedKeyring::load(&path, "work-password".into())
+        .await
+        .unwrap();
+    let item = keyring
+        .lookup_item(&[("service", "example")])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(item.secret(), oo7::Secret::text("secret"));
+}